@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -12,6 +14,16 @@ pub enum Data {
     #[serde(rename = "tabulated nk")]
     TabulatedNK { data: String },
 
+    /// The nonlinear refractive index against wavelength. Pages may give the
+    /// unit of n2 and how it was measured; n2 is in m²/W if no unit is given.
+    #[serde(rename = "tabulated n2")]
+    TabulatedN2 {
+        data: String,
+        unit: Option<String>,
+        method: Option<String>,
+        polarization: Option<String>,
+    },
+
     #[serde(rename = "formula 1")]
     Formula1 {
         wavelength_range: String,
@@ -65,6 +77,19 @@ pub enum Data {
         wavelength_range: String,
         coefficients: String,
     },
+
+    /// Data of any other type, such as a type added to the database after
+    /// this version of ria. Data of a known type with missing or malformed
+    /// fields also end up here.
+    #[serde(untagged)]
+    Unknown {
+        #[serde(rename = "type")]
+        type_name: String,
+
+        /// The other fields of the data.
+        #[serde(flatten)]
+        raw: serde_yaml::Mapping,
+    },
 }
 
 /// Represents a material as represented in the refractiveindex.info database.
@@ -79,10 +104,15 @@ pub struct RIInfoMaterial {
     #[serde(rename = "DATA")]
     pub data: Vec<Data>,
 
-    /// Do not serialize/deserialize SPECS for now because it is largely
-    /// unstructured.
-    #[serde(rename = "SPECS", skip)]
-    pub specs: String,
+    /// SPECS are largely unstructured, so they are kept as YAML and only the
+    /// values that the store uses are read from them.
+    #[serde(rename = "SPECS", default)]
+    pub specs: Option<serde_yaml::Value>,
+
+    /// Any other top-level keys, which are not used but kept so that they can
+    /// be reported.
+    #[serde(flatten)]
+    pub unknown: BTreeMap<String, serde_yaml::Value>,
 }
 
 #[cfg(test)]
@@ -131,6 +161,42 @@ mod test {
     fn test_deserialize_material() {
         let material: crate::database::material::RIInfoMaterial =
             serde_yaml::from_str(yaml()).unwrap();
-        assert_eq!(material.specs, "");
+        let specs = material.specs.unwrap();
+        assert_eq!(specs["nd"].as_f64(), Some(1.7847));
+        assert_eq!(specs["Vd"].as_f64(), Some(26.08));
+        assert!(material.unknown.is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "cli")]
+    fn test_deserialize_unknown_type_and_keys() {
+        use crate::database::material::{Data, RIInfoMaterial};
+
+        let yaml = r#"
+        REFERENCES: "Future reference"
+        COMMENTS: ""
+        DATA:
+          - type: tabulated n
+            data: |
+                0.5 1.5
+          - type: formula 42
+            wavelength_range: 0.3 2.5
+            coefficients: 1 2 3
+        CONDITIONS:
+            temperature: 293 K
+        "#;
+        let material: RIInfoMaterial = serde_yaml::from_str(yaml).unwrap();
+        assert!(matches!(material.data[0], Data::TabulatedN { .. }));
+        match &material.data[1] {
+            Data::Unknown { type_name, raw } => {
+                assert_eq!(type_name, "formula 42");
+                assert_eq!(raw["coefficients"].as_str(), Some("1 2 3"));
+            }
+            data => panic!("Expected unknown data, got {:?}", data),
+        }
+        assert_eq!(
+            material.unknown.keys().collect::<Vec<_>>(),
+            vec!["CONDITIONS"]
+        );
     }
 }
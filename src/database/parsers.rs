@@ -1,7 +1,7 @@
 use anyhow::{anyhow, Result};
 
 use crate::database::{Data, RIInfoMaterial};
-use crate::{DispersionData, Material};
+use crate::{CatalogKind, DispersionData, Material, Specs};
 
 pub(super) fn parse_material(
     material: RIInfoMaterial,
@@ -10,14 +10,41 @@ pub(super) fn parse_material(
     page: &str,
     shelf_divider: Option<String>,
     book_divider: Option<String>,
+    catalog: CatalogKind,
 ) -> Result<Material> {
     // TODO: Ignore any errors and continue parsing;
     // TODO: Log the errors
-    let data = material
+    let mut data = material
         .data
         .into_iter()
         .map(|data| data.try_into())
         .collect::<Result<Vec<_>>>()?;
+
+    // The measurement conditions of n2 data may be given in the SPECS
+    // rather than with the data
+    if let Some(specs) = &material.specs {
+        let condition = |name: &str| specs.get(name).and_then(|v| v.as_str()).map(String::from);
+        for data in &mut data {
+            if let DispersionData::TabulatedN2 {
+                method,
+                polarization,
+                ..
+            } = data
+            {
+                *method = method.take().or_else(|| condition("method"));
+                *polarization = polarization.take().or_else(|| condition("polarization"));
+            }
+        }
+    }
+
+    // SPECS that give only measurement conditions have no specs to keep
+    let specs = material
+        .specs
+        .map(|specs| Specs {
+            nd: specs.get("nd").and_then(serde_yaml::Value::as_f64),
+            vd: specs.get("Vd").and_then(serde_yaml::Value::as_f64),
+        })
+        .filter(|specs| specs.nd.is_some() || specs.vd.is_some());
     Ok(Material {
         shelf: shelf.to_string(),
         book: book.to_string(),
@@ -27,6 +54,8 @@ pub(super) fn parse_material(
         data,
         shelf_divider,
         book_divider,
+        specs,
+        catalog,
     })
 }
 
@@ -66,6 +95,19 @@ pub(super) fn parse_tabulated_2d(data: &str) -> Result<Vec<[f64; 2]>> {
         .collect()
 }
 
+/// Returns the factor that converts n2 in the given unit to m²/W.
+pub(super) fn n2_unit_scale(unit: Option<&str>) -> Result<f64> {
+    let Some(unit) = unit else {
+        return Ok(1.0);
+    };
+    // m2/W, m^2/W and m²/W are all accepted
+    match unit.replace('²', "2").replace(['^', ' '], "").as_str() {
+        "m2/W" => Ok(1.0),
+        "cm2/W" => Ok(1e-4),
+        _ => Err(anyhow!("Unknown unit of n2: {}", unit)),
+    }
+}
+
 pub(super) fn parse_tabulated_3d(data: &str) -> Result<Vec<[f64; 3]>> {
     data.lines()
         .map(|line| {
@@ -87,6 +129,24 @@ pub(super) fn parse_tabulated_3d(data: &str) -> Result<Vec<[f64; 3]>> {
         .collect()
 }
 
+/// The data types that have their own variant of [`DispersionData`]. Data of
+/// these types that still end up as [`Data::Unknown`] are malformed.
+const KNOWN_TYPES: [&str; 13] = [
+    "tabulated k",
+    "tabulated n",
+    "tabulated nk",
+    "tabulated n2",
+    "formula 1",
+    "formula 2",
+    "formula 3",
+    "formula 4",
+    "formula 5",
+    "formula 6",
+    "formula 7",
+    "formula 8",
+    "formula 9",
+];
+
 impl TryFrom<Data> for DispersionData {
     type Error = anyhow::Error;
 
@@ -104,6 +164,23 @@ impl TryFrom<Data> for DispersionData {
                 let data = parse_tabulated_3d(&data)?;
                 Ok(DispersionData::TabulatedNK { data })
             }
+            Data::TabulatedN2 {
+                data,
+                unit,
+                method,
+                polarization,
+            } => {
+                let scale = n2_unit_scale(unit.as_deref())?;
+                let data = parse_tabulated_2d(&data)?
+                    .into_iter()
+                    .map(|[wavelength, n2]| [wavelength, n2 * scale])
+                    .collect();
+                Ok(DispersionData::TabulatedN2 {
+                    data,
+                    method,
+                    polarization,
+                })
+            }
             Data::Formula1 {
                 wavelength_range,
                 coefficients,
@@ -203,6 +280,13 @@ impl TryFrom<Data> for DispersionData {
                     c: coefficients,
                 })
             }
+            Data::Unknown { type_name, .. } if KNOWN_TYPES.contains(&type_name.as_str()) => Err(
+                anyhow!("The {} data have missing or malformed fields", type_name),
+            ),
+            Data::Unknown { type_name, raw } => Ok(DispersionData::Unknown {
+                type_name,
+                raw: serde_yaml::to_string(&raw)?,
+            }),
         }
     }
 }
@@ -225,7 +309,10 @@ mod test {
                     "0.0 1.03961212 0.00600069867 0.231792344 0.0200179144 1.01046945 103.560653"
                         .to_string(),
             }],
-            specs: "".to_string(),
+            specs: Some(
+                serde_yaml::from_str("nd: 1.5168\nVd: 64.17\nglass_status: standard").unwrap(),
+            ),
+            unknown: Default::default(),
         };
 
         let result = parse_material(
@@ -235,6 +322,7 @@ mod test {
             "Johnson",
             Some("Ag - Silver".to_string()),
             Some("Bulk".to_string()),
+            CatalogKind::N2,
         )
         .unwrap();
 
@@ -246,6 +334,14 @@ mod test {
         assert_eq!(result.shelf_divider, Some("Ag - Silver".to_string()));
         assert_eq!(result.book_divider, Some("Bulk".to_string()));
         assert_eq!(result.data.len(), 1);
+        assert_eq!(result.catalog, CatalogKind::N2);
+        assert_eq!(
+            result.specs,
+            Some(Specs {
+                nd: Some(1.5168),
+                vd: Some(64.17)
+            })
+        );
     }
 
     #[test]
@@ -275,4 +371,93 @@ mod test {
         let result = parse_tabulated_3d(data).unwrap();
         assert_eq!(result, [[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]);
     }
+
+    #[test]
+    fn test_n2_unit_scale() {
+        assert_eq!(n2_unit_scale(None).unwrap(), 1.0);
+        assert_eq!(n2_unit_scale(Some("m2/W")).unwrap(), 1.0);
+        assert_eq!(n2_unit_scale(Some("cm^2/W")).unwrap(), 1e-4);
+        assert_eq!(n2_unit_scale(Some("cm²/W")).unwrap(), 1e-4);
+        assert!(n2_unit_scale(Some("esu")).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "cli")]
+    fn test_parse_n2_page() {
+        use crate::database::RIInfoMaterial;
+
+        let page = r#"
+REFERENCES: "D. Milam. Appl. Opt. 37, 546-550 (1998)"
+COMMENTS: "Fused silica"
+DATA:
+  - type: tabulated n2
+    unit: cm2/W
+    polarization: linear
+    data: |
+        0.355 3.6e-16
+        1.064 2.14e-16
+SPECS:
+  method: Z-scan
+  polarization: circular
+"#;
+        let material: RIInfoMaterial = serde_yaml::from_str(page).unwrap();
+        let result = parse_material(
+            material,
+            "main",
+            "SiO2",
+            "Milam",
+            None,
+            None,
+            CatalogKind::N2,
+        )
+        .unwrap();
+
+        match &result.data[0] {
+            DispersionData::TabulatedN2 {
+                data,
+                method,
+                polarization,
+            } => {
+                assert_eq!(data, &[[0.355, 3.6e-20], [1.064, 2.14e-20]]);
+                // The SPECS only fill in what the data do not give
+                assert_eq!(method.as_deref(), Some("Z-scan"));
+                assert_eq!(polarization.as_deref(), Some("linear"));
+            }
+            data => panic!("Expected tabulated n2 data, got {}", data.type_name()),
+        }
+        assert_eq!(result.n2(0.355).unwrap(), 3.6e-20);
+        assert!(result.specs.is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "cli")]
+    fn test_parse_unknown_data_type() {
+        use crate::database::RIInfoMaterial;
+
+        let page = r#"
+REFERENCES: ""
+COMMENTS: ""
+DATA:
+  - type: formula 42
+    wavelength_range: 0.3 2.5
+    coefficients: 1 2 3
+"#;
+        let material: RIInfoMaterial = serde_yaml::from_str(page).unwrap();
+        let result =
+            parse_material(material, "main", "X", "Y", None, None, CatalogKind::Nk).unwrap();
+        match &result.data[0] {
+            DispersionData::Unknown { type_name, raw } => {
+                assert_eq!(type_name, "formula 42");
+                assert!(raw.contains("coefficients: 1 2 3"));
+            }
+            data => panic!("Expected unknown data, got {}", data.type_name()),
+        }
+
+        // A known type with a missing field is an error, not unknown data
+        let page = "REFERENCES: \"\"\nCOMMENTS: \"\"\nDATA:\n  - type: formula 2\n    coefficients: 1 2 3\n";
+        let material: RIInfoMaterial = serde_yaml::from_str(page).unwrap();
+        let err =
+            parse_material(material, "main", "X", "Y", None, None, CatalogKind::Nk).unwrap_err();
+        assert!(err.to_string().contains("formula 2"));
+    }
 }
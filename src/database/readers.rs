@@ -1,12 +1,14 @@
 //! Reads the input database files from disk.
+use std::collections::BTreeMap;
+
 use anyhow::Error;
 
 use crate::database::parsers::parse_material;
 use crate::database::{BookContent, Catalog, CatalogEntry, RIInfoMaterial, ShelfContent};
-use crate::internal::store::Store;
+use crate::internal::store::{CatalogKind, DispersionData, Store};
 
 #[cfg(feature = "cli")]
-pub(crate) fn read_material(path: std::path::PathBuf) -> Result<RIInfoMaterial, Error> {
+pub(crate) fn read_material(path: &std::path::Path) -> Result<RIInfoMaterial, Error> {
     let text = std::fs::read_to_string(path)?;
     let material: RIInfoMaterial = serde_yaml::from_str(&text)?;
     Ok(material)
@@ -15,10 +17,28 @@ pub(crate) fn read_material(path: std::path::PathBuf) -> Result<RIInfoMaterial,
 impl TryFrom<Catalog> for Store {
     type Error = anyhow::Error;
 
-    /// Converts a RefractiveIndex.INFO catalog into a flat, key-value store of
-    /// materials data.
+    /// Converts a RefractiveIndex.INFO nk catalog into a flat, key-value store
+    /// of materials data.
     fn try_from(catalog: Catalog) -> Result<Self, Self::Error> {
+        Store::from_catalog(catalog, CatalogKind::Nk)
+    }
+}
+
+impl Store {
+    /// Converts a RefractiveIndex.INFO catalog into a flat, key-value store of
+    /// materials data. Pages whose data cannot be read or parsed are skipped
+    /// with a warning. Unknown top-level keys of pages are ignored and data of
+    /// unsupported types are kept, and both are summarized in warnings.
+    ///
+    /// # Arguments
+    /// - `catalog`: The parsed catalog file. Material data are read relative to
+    ///   the current directory.
+    /// - `kind`: The catalog that the file describes, which every material is
+    ///   tagged with.
+    pub fn from_catalog(catalog: Catalog, kind: CatalogKind) -> Result<Store, Error> {
         let mut store = Store::default();
+        let mut unknown_keys: BTreeMap<String, usize> = BTreeMap::new();
+        let mut unknown_types: BTreeMap<String, usize> = BTreeMap::new();
 
         for entry in catalog {
             let shelf = match entry {
@@ -67,15 +87,27 @@ impl TryFrom<Catalog> for Store {
 
                             let page_key = &page;
                             let page_name = &name;
+                            let key = format!("{}:{}:{}", shelf_key, book_key, page_key);
 
                             // Try to read the material data; if it fails, skip this page
-                            let material = match read_material(data) {
+                            let material = match read_material(&data) {
                                 Ok(material) => material,
-                                Err(_) => {
+                                Err(err) => {
+                                    log::warn!(
+                                        "Skipping {}: cannot read {}: {}",
+                                        key,
+                                        data.display(),
+                                        err
+                                    );
                                     continue;
                                 }
                             };
 
+                            for name in material.unknown.keys() {
+                                log::debug!("{} has the unknown key {}", key, name);
+                                *unknown_keys.entry(name.clone()).or_default() += 1;
+                            }
+
                             // Parse the material data
                             let item = match parse_material(
                                 material,
@@ -84,14 +116,23 @@ impl TryFrom<Catalog> for Store {
                                 page_name,
                                 current_shelf_divider.clone(),
                                 current_book_divider.clone(),
+                                kind,
                             ) {
                                 Ok(item) => item,
-                                Err(_) => {
+                                Err(err) => {
+                                    log::warn!("Skipping {}: {}", key, err);
                                     continue;
                                 }
                             };
 
-                            let key = format!("{}:{}:{}", shelf_key, book_key, page_key);
+                            for data in &item.data {
+                                if let DispersionData::Unknown { type_name, .. } = data {
+                                    log::debug!("{} has data of the type {}", key, type_name);
+                                    *unknown_types.entry(type_name.clone()).or_default() += 1;
+                                }
+                            }
+
+                            log::debug!("Read {}", key);
                             store.insert(key, item);
                         }
                     }
@@ -99,6 +140,23 @@ impl TryFrom<Catalog> for Store {
             }
         }
 
+        for (name, count) in unknown_keys {
+            log::warn!(
+                "Ignored the unknown key {} of {} page{}",
+                name,
+                count,
+                if count == 1 { "" } else { "s" }
+            );
+        }
+        for (type_name, count) in unknown_types {
+            log::warn!(
+                "Kept {} set{} of data of the unsupported type \"{}\", which cannot be evaluated",
+                count,
+                if count == 1 { "" } else { "s" },
+                type_name
+            );
+        }
+
         Ok(store)
     }
 }
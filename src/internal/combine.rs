@@ -0,0 +1,144 @@
+//! Stores that combine the nk and n2 catalogs of the database.
+use crate::internal::store::{CatalogKind, Material, Store};
+
+/// The prefix of the keys of n2 materials in a combined store.
+pub const N2_KEY_PREFIX: &str = "n2:";
+
+/// Returns the prefix of the keys of the n2 materials in the same shelf and
+/// book as a key in a combined store, e.g. `n2:glass:BK7:` for
+/// `glass:BK7:SCHOTT`.
+///
+/// # Arguments
+/// - `key`: The key of a material, with or without the n2 prefix, or just its
+///   `shelf:book`.
+///
+/// # Returns
+/// The prefix, or `None` if the key has no book.
+pub fn n2_key_prefix(key: &str) -> Option<String> {
+    let mut parts = key_parts(key);
+    let (Some(shelf), Some(book)) = (parts.next(), parts.next()) else {
+        return None;
+    };
+    Some(format!("{}{}:{}:", N2_KEY_PREFIX, shelf, book))
+}
+
+/// Splits a key into its shelf, book and page, ignoring the prefix of the n2
+/// keys of a combined store, so that `n2:glass:BK7:Milam` is in the shelf
+/// glass like `glass:BK7:SCHOTT`.
+///
+/// # Arguments
+/// - `key`: The key, e.g. glass:BK7:SCHOTT.
+pub(crate) fn key_parts(key: &str) -> std::str::SplitN<'_, char> {
+    key.strip_prefix(N2_KEY_PREFIX)
+        .unwrap_or(key)
+        .splitn(3, ':')
+}
+
+impl Store {
+    /// Combines stores read from the nk and n2 catalogs into a single store.
+    ///
+    /// The materials of the nk store keep their keys, so a combined store can
+    /// be used wherever an nk store is expected. The keys of the n2
+    /// materials are prefixed with [`N2_KEY_PREFIX`], as both catalogs may
+    /// contain the same `shelf:book:page`. Every material is tagged with the
+    /// catalog it came from.
+    ///
+    /// # Arguments
+    /// - `nk`: The store read from the nk catalog.
+    /// - `n2`: The store read from the n2 catalog.
+    pub fn combine(nk: Store, n2: Store) -> Store {
+        let mut store = Store::default();
+        for (key, mut material) in nk {
+            material.catalog = CatalogKind::Nk;
+            store.insert(key, material);
+        }
+        for (key, mut material) in n2 {
+            material.catalog = CatalogKind::N2;
+            store.insert(format!("{}{}", N2_KEY_PREFIX, key), material);
+        }
+        store
+    }
+
+    /// Returns the n2 materials in the same shelf and book as a material of
+    /// a combined store, so that the nonlinear data of a material can be
+    /// found from the key of its linear data.
+    ///
+    /// # Arguments
+    /// - `key`: The key of a material, with or without the n2 prefix, or just
+    ///   its `shelf:book`.
+    ///
+    /// # Returns
+    /// The keys and n2 materials of the book, sorted by key.
+    pub fn n2_of(&self, key: &str) -> Vec<(&String, &Material)> {
+        let Some(prefix) = n2_key_prefix(key) else {
+            return Vec::new();
+        };
+
+        let mut materials: Vec<_> = self
+            .iter()
+            .filter(|(key, material)| {
+                material.catalog == CatalogKind::N2 && key.starts_with(&prefix)
+            })
+            .collect();
+        materials.sort_by_key(|(key, _)| *key);
+        materials
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn material(page: &str) -> Material {
+        Material {
+            shelf: "GLASS - optical glasses".to_string(),
+            book: "N-BK7".to_string(),
+            page: page.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn combined() -> Store {
+        let mut nk = Store::default();
+        nk.insert("glass:BK7:SCHOTT".to_string(), material("SCHOTT"));
+        nk.insert("glass:SF11:SCHOTT".to_string(), material("SCHOTT"));
+        let mut n2 = Store::default();
+        n2.insert("glass:BK7:SCHOTT".to_string(), material("SCHOTT"));
+        n2.insert("glass:BK7:Milam".to_string(), material("Milam 1998"));
+        n2.insert("glass:F2:Milam".to_string(), material("Milam 1998"));
+        Store::combine(nk, n2)
+    }
+
+    #[test]
+    fn test_combine_namespaces_and_tags_n2() {
+        let store = combined();
+        assert_eq!(store.keys().count(), 5);
+        assert_eq!(
+            store.get("glass:BK7:SCHOTT").unwrap().catalog,
+            CatalogKind::Nk
+        );
+        assert_eq!(
+            store.get("n2:glass:BK7:SCHOTT").unwrap().catalog,
+            CatalogKind::N2
+        );
+        assert!(store.get("glass:F2:Milam").is_none());
+    }
+
+    #[test]
+    fn test_n2_of() {
+        let store = combined();
+        let keys = |key: &str| {
+            store
+                .n2_of(key)
+                .into_iter()
+                .map(|(key, _)| key.as_str())
+                .collect::<Vec<_>>()
+        };
+        let bk7 = vec!["n2:glass:BK7:Milam", "n2:glass:BK7:SCHOTT"];
+        assert_eq!(keys("glass:BK7:SCHOTT"), bk7);
+        assert_eq!(keys("n2:glass:BK7:Milam"), bk7);
+        assert_eq!(keys("glass:BK7"), bk7);
+        assert!(keys("glass:SF11:SCHOTT").is_empty());
+        assert!(keys("glass").is_empty());
+    }
+}
@@ -0,0 +1,558 @@
+//! A compact binary store format.
+//!
+//! Many pages share long strings, such as the references of a whole vendor
+//! catalog, and tabulated data rarely need double precision. The compact
+//! format stores each distinct string once and references it by index, and
+//! stores tabulated data column by column, optionally as `f32`.
+//!
+//! The compact format is only a serialization format. It is expanded into a
+//! [`Store`] when it is read, so the [`Material`] API is unchanged.
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::internal::store::{CatalogKind, DispersionData, Material, Specs, Store};
+
+/// The first bytes of every compact store.
+const MAGIC: &[u8; 6] = b"RIACMP";
+
+/// The version of the compact layout written by this library. It must be
+/// incremented whenever the layout of [`CompactStore`] changes.
+const FORMAT_VERSION: u16 = 1;
+
+/// The precision of the tabulated data in a compact store.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TablePrecision {
+    /// Tabulated data are stored with full precision.
+    #[default]
+    F64,
+
+    /// Tabulated data are rounded to single precision, about 7 significant
+    /// digits.
+    F32,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct CompactStore {
+    strings: Vec<String>,
+    materials: Vec<CompactMaterial>,
+}
+
+/// A material whose strings are indexes into [`CompactStore::strings`].
+#[derive(Serialize, Deserialize, Debug)]
+struct CompactMaterial {
+    key: u32,
+    shelf: u32,
+    book: u32,
+    page: u32,
+    comments: u32,
+    references: u32,
+    data: Vec<CompactData>,
+    shelf_divider: Option<u32>,
+    book_divider: Option<u32>,
+    specs: Option<Specs>,
+    catalog: CatalogKind,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+enum CompactData {
+    TabulatedK {
+        wavelength: Column,
+        k: Column,
+    },
+    TabulatedN {
+        wavelength: Column,
+        n: Column,
+    },
+    TabulatedNK {
+        wavelength: Column,
+        n: Column,
+        k: Column,
+    },
+    TabulatedN2 {
+        wavelength: Column,
+        n2: Column,
+        method: Option<String>,
+        polarization: Option<String>,
+    },
+
+    /// Any of the dispersion formulas, identified by their number.
+    Formula {
+        formula: u8,
+        wavelength_range: [f64; 2],
+        c: Vec<f64>,
+    },
+
+    Unknown {
+        type_name: String,
+        raw: String,
+    },
+}
+
+/// One column of a table of tabulated data.
+#[derive(Serialize, Deserialize, Debug)]
+enum Column {
+    F64(Vec<f64>),
+    F32(Vec<f32>),
+}
+
+impl Column {
+    fn new(values: impl Iterator<Item = f64>, precision: TablePrecision) -> Self {
+        match precision {
+            TablePrecision::F64 => Column::F64(values.collect()),
+            TablePrecision::F32 => Column::F32(values.map(|v| v as f32).collect()),
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Column::F64(values) => values.len(),
+            Column::F32(values) => values.len(),
+        }
+    }
+
+    fn get(&self, i: usize) -> f64 {
+        match self {
+            Column::F64(values) => values[i],
+            Column::F32(values) => values[i] as f64,
+        }
+    }
+}
+
+/// Assigns each distinct string an index into a table of strings.
+#[derive(Default)]
+struct Interner {
+    strings: Vec<String>,
+    indexes: HashMap<String, u32>,
+}
+
+impl Interner {
+    fn intern(&mut self, s: &str) -> u32 {
+        if let Some(&index) = self.indexes.get(s) {
+            return index;
+        }
+        let index = self.strings.len() as u32;
+        self.strings.push(s.to_string());
+        self.indexes.insert(s.to_string(), index);
+        index
+    }
+}
+
+impl CompactData {
+    fn new(data: &DispersionData, precision: TablePrecision) -> Self {
+        match data {
+            DispersionData::TabulatedK { data } => CompactData::TabulatedK {
+                wavelength: Column::new(data.iter().map(|r| r[0]), precision),
+                k: Column::new(data.iter().map(|r| r[1]), precision),
+            },
+            DispersionData::TabulatedN { data } => CompactData::TabulatedN {
+                wavelength: Column::new(data.iter().map(|r| r[0]), precision),
+                n: Column::new(data.iter().map(|r| r[1]), precision),
+            },
+            DispersionData::TabulatedNK { data } => CompactData::TabulatedNK {
+                wavelength: Column::new(data.iter().map(|r| r[0]), precision),
+                n: Column::new(data.iter().map(|r| r[1]), precision),
+                k: Column::new(data.iter().map(|r| r[2]), precision),
+            },
+            DispersionData::TabulatedN2 {
+                data,
+                method,
+                polarization,
+            } => CompactData::TabulatedN2 {
+                wavelength: Column::new(data.iter().map(|r| r[0]), precision),
+                n2: Column::new(data.iter().map(|r| r[1]), precision),
+                method: method.clone(),
+                polarization: polarization.clone(),
+            },
+            DispersionData::Formula1 {
+                wavelength_range,
+                c,
+            } => CompactData::formula(1, wavelength_range, c),
+            DispersionData::Formula2 {
+                wavelength_range,
+                c,
+            } => CompactData::formula(2, wavelength_range, c),
+            DispersionData::Formula3 {
+                wavelength_range,
+                c,
+            } => CompactData::formula(3, wavelength_range, c),
+            DispersionData::Formula4 {
+                wavelength_range,
+                c,
+            } => CompactData::formula(4, wavelength_range, c),
+            DispersionData::Formula5 {
+                wavelength_range,
+                c,
+            } => CompactData::formula(5, wavelength_range, c),
+            DispersionData::Formula6 {
+                wavelength_range,
+                c,
+            } => CompactData::formula(6, wavelength_range, c),
+            DispersionData::Formula7 {
+                wavelength_range,
+                c,
+            } => CompactData::formula(7, wavelength_range, c),
+            DispersionData::Formula8 {
+                wavelength_range,
+                c,
+            } => CompactData::formula(8, wavelength_range, c),
+            DispersionData::Formula9 {
+                wavelength_range,
+                c,
+            } => CompactData::formula(9, wavelength_range, c),
+            DispersionData::Unknown { type_name, raw } => CompactData::Unknown {
+                type_name: type_name.clone(),
+                raw: raw.clone(),
+            },
+        }
+    }
+
+    fn formula(formula: u8, wavelength_range: &[f64; 2], c: &[f64]) -> Self {
+        CompactData::Formula {
+            formula,
+            wavelength_range: *wavelength_range,
+            c: c.to_vec(),
+        }
+    }
+}
+
+impl TryFrom<CompactData> for DispersionData {
+    type Error = anyhow::Error;
+
+    fn try_from(data: CompactData) -> Result<Self> {
+        let rows = |columns: &[&Column]| -> Result<Vec<Vec<f64>>> {
+            let len = columns[0].len();
+            if columns.iter().any(|column| column.len() != len) {
+                return Err(anyhow!("The columns of tabulated data differ in length."));
+            }
+            Ok((0..len)
+                .map(|i| columns.iter().map(|column| column.get(i)).collect())
+                .collect())
+        };
+
+        Ok(match data {
+            CompactData::TabulatedK { wavelength, k } => DispersionData::TabulatedK {
+                data: rows(&[&wavelength, &k])?
+                    .into_iter()
+                    .map(|r| [r[0], r[1]])
+                    .collect(),
+            },
+            CompactData::TabulatedN { wavelength, n } => DispersionData::TabulatedN {
+                data: rows(&[&wavelength, &n])?
+                    .into_iter()
+                    .map(|r| [r[0], r[1]])
+                    .collect(),
+            },
+            CompactData::TabulatedNK { wavelength, n, k } => DispersionData::TabulatedNK {
+                data: rows(&[&wavelength, &n, &k])?
+                    .into_iter()
+                    .map(|r| [r[0], r[1], r[2]])
+                    .collect(),
+            },
+            CompactData::TabulatedN2 {
+                wavelength,
+                n2,
+                method,
+                polarization,
+            } => DispersionData::TabulatedN2 {
+                data: rows(&[&wavelength, &n2])?
+                    .into_iter()
+                    .map(|r| [r[0], r[1]])
+                    .collect(),
+                method,
+                polarization,
+            },
+            CompactData::Formula {
+                formula,
+                wavelength_range,
+                c,
+            } => match formula {
+                1 => DispersionData::Formula1 {
+                    wavelength_range,
+                    c,
+                },
+                2 => DispersionData::Formula2 {
+                    wavelength_range,
+                    c,
+                },
+                3 => DispersionData::Formula3 {
+                    wavelength_range,
+                    c,
+                },
+                4 => DispersionData::Formula4 {
+                    wavelength_range,
+                    c,
+                },
+                5 => DispersionData::Formula5 {
+                    wavelength_range,
+                    c,
+                },
+                6 => DispersionData::Formula6 {
+                    wavelength_range,
+                    c,
+                },
+                7 => DispersionData::Formula7 {
+                    wavelength_range,
+                    c,
+                },
+                8 => DispersionData::Formula8 {
+                    wavelength_range,
+                    c,
+                },
+                9 => DispersionData::Formula9 {
+                    wavelength_range,
+                    c,
+                },
+                _ => return Err(anyhow!("Unknown dispersion formula {}", formula)),
+            },
+            CompactData::Unknown { type_name, raw } => DispersionData::Unknown { type_name, raw },
+        })
+    }
+}
+
+impl Store {
+    /// Serializes the store in the compact format.
+    ///
+    /// # Arguments
+    /// - `precision`: The precision with which to store tabulated data.
+    ///
+    /// # Errors
+    /// - If the store cannot be serialized.
+    pub fn to_compact(&self, precision: TablePrecision) -> Result<Vec<u8>> {
+        let mut keys: Vec<&String> = self.keys().collect();
+        keys.sort();
+
+        let mut interner = Interner::default();
+        let materials = keys
+            .into_iter()
+            .map(|key| {
+                let material = self.get(key).unwrap();
+                CompactMaterial {
+                    key: interner.intern(key),
+                    shelf: interner.intern(&material.shelf),
+                    book: interner.intern(&material.book),
+                    page: interner.intern(&material.page),
+                    comments: interner.intern(&material.comments),
+                    references: interner.intern(&material.references),
+                    data: material
+                        .data
+                        .iter()
+                        .map(|data| CompactData::new(data, precision))
+                        .collect(),
+                    shelf_divider: material
+                        .shelf_divider
+                        .as_deref()
+                        .map(|s| interner.intern(s)),
+                    book_divider: material.book_divider.as_deref().map(|s| interner.intern(s)),
+                    specs: material.specs,
+                    catalog: material.catalog,
+                }
+            })
+            .collect();
+
+        let compact = CompactStore {
+            strings: interner.strings,
+            materials,
+        };
+
+        let mut data = Vec::new();
+        data.extend_from_slice(MAGIC);
+        data.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        data.extend_from_slice(&bitcode::serialize(&compact)?);
+        Ok(data)
+    }
+
+    /// Reads a store in the compact format.
+    ///
+    /// # Arguments
+    /// - `data`: The serialized store.
+    ///
+    /// # Errors
+    /// - If the data are not a compact store or were written with an
+    ///   unsupported format version.
+    /// - If the data cannot be deserialized.
+    pub fn from_compact_bytes(data: &[u8]) -> Result<Store> {
+        let header_len = MAGIC.len() + 2;
+        if data.len() < header_len || &data[..MAGIC.len()] != MAGIC {
+            return Err(anyhow!("The data are not a compact store."));
+        }
+
+        let version = u16::from_le_bytes([data[6], data[7]]);
+        if version != FORMAT_VERSION {
+            return Err(anyhow!(
+                "Unsupported compact store version {}; expected {}",
+                version,
+                FORMAT_VERSION
+            ));
+        }
+
+        let compact: CompactStore = bitcode::deserialize(&data[header_len..])?;
+        let strings = compact.strings;
+        let string = |index: u32| -> Result<String> {
+            strings
+                .get(index as usize)
+                .cloned()
+                .ok_or(anyhow!("String index {} is out of bounds.", index))
+        };
+
+        let mut store = Store::default();
+        for material in compact.materials {
+            let item = Material {
+                shelf: string(material.shelf)?,
+                book: string(material.book)?,
+                page: string(material.page)?,
+                comments: string(material.comments)?,
+                references: string(material.references)?,
+                data: material
+                    .data
+                    .into_iter()
+                    .map(|data| data.try_into())
+                    .collect::<Result<_>>()?,
+                shelf_divider: material.shelf_divider.map(string).transpose()?,
+                book_divider: material.book_divider.map(string).transpose()?,
+                specs: material.specs,
+                catalog: material.catalog,
+            };
+            store.insert(string(material.key)?, item);
+        }
+
+        Ok(store)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn test_material(page: &str, data: Vec<DispersionData>) -> Material {
+        Material {
+            shelf: "GLASS - optical glass".to_string(),
+            book: "N-BK7".to_string(),
+            page: page.to_string(),
+            references: "SCHOTT Zemax catalog 2017-01-20b".to_string(),
+            data,
+            shelf_divider: Some("SCHOTT - optical glass".to_string()),
+            specs: Some(Specs {
+                nd: Some(1.5168),
+                vd: Some(64.17),
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn test_store() -> Store {
+        let mut store = Store::default();
+        store.insert(
+            "glass:BK7:SCHOTT".to_string(),
+            test_material(
+                "SCHOTT",
+                vec![
+                    DispersionData::Formula2 {
+                        wavelength_range: [0.3, 2.5],
+                        c: vec![0.0, 1.03961212, 0.00600069867],
+                    },
+                    DispersionData::TabulatedK {
+                        data: vec![[0.31, 2.8607e-6], [0.32, 1.3679e-6]],
+                    },
+                ],
+            ),
+        );
+        store.insert(
+            "main:Ag:Johnson".to_string(),
+            test_material(
+                "Johnson",
+                vec![DispersionData::TabulatedNK {
+                    data: vec![[0.4, 0.05, 2.1], [0.5, 0.05, 3.1]],
+                }],
+            ),
+        );
+        store.insert(
+            "n2:glass:BK7:Milam".to_string(),
+            test_material(
+                "Milam",
+                vec![DispersionData::TabulatedN2 {
+                    data: vec![[0.532, 2.4e-20], [1.064, 2.14e-20]],
+                    method: Some("Z-scan".to_string()),
+                    polarization: None,
+                }],
+            ),
+        );
+        store
+    }
+
+    #[test]
+    fn test_compact_roundtrip_f64() {
+        let data = test_store().to_compact(TablePrecision::F64).unwrap();
+        let store = Store::from_compact_bytes(&data).unwrap();
+
+        let material = store.get("glass:BK7:SCHOTT").unwrap();
+        assert_eq!(material.page, "SCHOTT");
+        assert_eq!(material.references, "SCHOTT Zemax catalog 2017-01-20b");
+        assert_eq!(
+            material.shelf_divider,
+            Some("SCHOTT - optical glass".to_string())
+        );
+        assert!(material.book_divider.is_none());
+        assert_eq!(material.specs.unwrap().nd, Some(1.5168));
+        match &material.data[..] {
+            [DispersionData::Formula2 {
+                wavelength_range,
+                c,
+            }, DispersionData::TabulatedK { data }] => {
+                assert_eq!(wavelength_range, &[0.3, 2.5]);
+                assert_eq!(c, &[0.0, 1.03961212, 0.00600069867]);
+                assert_eq!(data, &[[0.31, 2.8607e-6], [0.32, 1.3679e-6]]);
+            }
+            data => panic!("unexpected data: {:?}", data),
+        }
+
+        let material = store.get("n2:glass:BK7:Milam").unwrap();
+        match &material.data[..] {
+            [DispersionData::TabulatedN2 {
+                data,
+                method,
+                polarization,
+            }] => {
+                assert_eq!(data, &[[0.532, 2.4e-20], [1.064, 2.14e-20]]);
+                assert_eq!(method.as_deref(), Some("Z-scan"));
+                assert!(polarization.is_none());
+            }
+            data => panic!("unexpected data: {:?}", data),
+        }
+    }
+
+    #[test]
+    fn test_compact_roundtrip_f32() {
+        let data = test_store().to_compact(TablePrecision::F32).unwrap();
+        let store = Store::from_compact_bytes(&data).unwrap();
+
+        let material = store.get("main:Ag:Johnson").unwrap();
+        match &material.data[..] {
+            [DispersionData::TabulatedNK { data }] => {
+                assert_eq!(data.len(), 2);
+                assert_eq!(data[0][0], 0.4_f32 as f64);
+                assert_eq!(data[1][2], 3.1_f32 as f64);
+            }
+            data => panic!("unexpected data: {:?}", data),
+        }
+    }
+
+    #[test]
+    fn test_compact_interns_strings() {
+        let data = test_store().to_compact(TablePrecision::F64).unwrap();
+        let compact: CompactStore = bitcode::deserialize(&data[MAGIC.len() + 2..]).unwrap();
+        let references = compact
+            .strings
+            .iter()
+            .filter(|s| *s == "SCHOTT Zemax catalog 2017-01-20b")
+            .count();
+        assert_eq!(references, 1);
+    }
+
+    #[test]
+    fn test_compact_rejects_other_formats() {
+        let data = test_store().to_bitcode().unwrap();
+        assert!(Store::from_compact_bytes(&data).is_err());
+    }
+}
@@ -0,0 +1,188 @@
+//! Compression of serialized stores.
+//!
+//! Compressed stores are recognized by the magic numbers of their compression
+//! format, so any store may be compressed without recording it elsewhere.
+use std::borrow::Cow;
+use std::fmt;
+
+use anyhow::{anyhow, Result};
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+/// The compression formats of a store file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    /// Detects the compression format of the data from its magic number.
+    ///
+    /// # Arguments
+    /// - `data`: The possibly compressed data, or at least its first few bytes.
+    ///
+    /// # Returns
+    /// The compression format, or `None` if the data are not compressed.
+    pub fn detect(data: &[u8]) -> Option<Compression> {
+        if data.starts_with(GZIP_MAGIC) {
+            Some(Compression::Gzip)
+        } else if data.starts_with(ZSTD_MAGIC) {
+            Some(Compression::Zstd)
+        } else {
+            None
+        }
+    }
+
+    /// The compression level used when none is given.
+    pub fn default_level(&self) -> i32 {
+        match self {
+            Compression::Gzip => 6,
+            Compression::Zstd => 3,
+        }
+    }
+}
+
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Compression::Gzip => write!(f, "gzip"),
+            Compression::Zstd => write!(f, "zstd"),
+        }
+    }
+}
+
+/// Compresses data.
+///
+/// # Arguments
+/// - `data`: The data to compress.
+/// - `compression`: The compression format.
+/// - `level`: The compression level: 0 to 9 for gzip and at most 22 for zstd.
+///   If `None`, the format's default level is used.
+///
+/// # Errors
+/// - If the level is outside the range supported by the format.
+/// - If the format was not enabled when this crate was compiled.
+#[cfg_attr(not(all(feature = "gzip", feature = "zstd")), allow(unused_variables))]
+pub fn compress(data: &[u8], compression: Compression, level: Option<i32>) -> Result<Vec<u8>> {
+    let level = level.unwrap_or(compression.default_level());
+    match compression {
+        #[cfg(feature = "gzip")]
+        Compression::Gzip => {
+            use std::io::Write;
+
+            let level = u32::try_from(level)
+                .ok()
+                .filter(|level| *level <= 9)
+                .ok_or(anyhow!(
+                    "The gzip compression level must be between 0 and 9."
+                ))?;
+            let mut encoder =
+                flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::new(level));
+            encoder.write_all(data)?;
+            Ok(encoder.finish()?)
+        }
+        #[cfg(feature = "zstd")]
+        Compression::Zstd => {
+            if !zstd::compression_level_range().contains(&level) {
+                return Err(anyhow!(
+                    "The zstd compression level must be between {} and {}.",
+                    zstd::compression_level_range().start(),
+                    zstd::compression_level_range().end()
+                ));
+            }
+            Ok(zstd::encode_all(data, level)?)
+        }
+        #[allow(unreachable_patterns)]
+        compression => Err(anyhow!(
+            "{} compression requires the `{}` feature",
+            compression,
+            compression
+        )),
+    }
+}
+
+/// Decompresses data if they are compressed.
+///
+/// # Arguments
+/// - `data`: The possibly compressed data.
+///
+/// # Returns
+/// The decompressed data, or the input if it is not compressed.
+///
+/// # Errors
+/// - If the data cannot be decompressed.
+/// - If the data are compressed with a format that was not enabled when this
+///   crate was compiled.
+pub fn decompress(data: &[u8]) -> Result<Cow<'_, [u8]>> {
+    match Compression::detect(data) {
+        None => Ok(Cow::Borrowed(data)),
+        #[cfg(feature = "gzip")]
+        Some(Compression::Gzip) => {
+            use std::io::Read;
+
+            let mut decoded = Vec::new();
+            flate2::read::MultiGzDecoder::new(data).read_to_end(&mut decoded)?;
+            Ok(Cow::Owned(decoded))
+        }
+        #[cfg(feature = "zstd")]
+        Some(Compression::Zstd) => Ok(Cow::Owned(zstd::decode_all(data)?)),
+        #[allow(unreachable_patterns)]
+        Some(compression) => Err(anyhow!(
+            "The data are {} compressed, which requires the `{}` feature",
+            compression,
+            compression
+        )),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_detect_uncompressed() {
+        assert_eq!(Compression::detect(b"{\"inner\":{}}"), None);
+        assert_eq!(Compression::detect(&[]), None);
+    }
+
+    #[test]
+    fn test_decompress_uncompressed_is_borrowed() {
+        let data = b"{\"inner\":{}}";
+        assert!(matches!(decompress(data).unwrap(), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    #[cfg(feature = "gzip")]
+    fn test_gzip_roundtrip() {
+        let data = b"{\"inner\":{}}".repeat(100);
+        let compressed = compress(&data, Compression::Gzip, None).unwrap();
+        assert_eq!(Compression::detect(&compressed), Some(Compression::Gzip));
+        assert!(compressed.len() < data.len());
+        assert_eq!(decompress(&compressed).unwrap().as_ref(), data.as_slice());
+    }
+
+    #[test]
+    #[cfg(feature = "gzip")]
+    fn test_gzip_invalid_level() {
+        assert!(compress(b"data", Compression::Gzip, Some(10)).is_err());
+        assert!(compress(b"data", Compression::Gzip, Some(-1)).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "zstd")]
+    fn test_zstd_roundtrip() {
+        let data = b"{\"inner\":{}}".repeat(100);
+        let compressed = compress(&data, Compression::Zstd, Some(19)).unwrap();
+        assert_eq!(Compression::detect(&compressed), Some(Compression::Zstd));
+        assert!(compressed.len() < data.len());
+        assert_eq!(decompress(&compressed).unwrap().as_ref(), data.as_slice());
+    }
+
+    #[test]
+    #[cfg(feature = "zstd")]
+    fn test_zstd_invalid_level() {
+        assert!(compress(b"data", Compression::Zstd, Some(23)).is_err());
+    }
+}
@@ -0,0 +1,244 @@
+//! Composable predicates for selecting materials from a store.
+use crate::internal::combine::key_parts;
+use crate::internal::store::{DispersionData, Material};
+
+/// A predicate on the items of a store.
+///
+/// Filters can be combined with [`Filter::and`], [`Filter::or`] and
+/// [`Filter::not`], and applied with [`crate::Store::retain`]:
+///
+/// ```
+/// use lib_ria::{Filter, Store};
+///
+/// let filter = Filter::Shelf("glass".to_string()).and(Filter::Covers([0.4, 0.7]));
+/// let mut store = Store::default();
+/// store.retain(|key, material| filter.matches(key, material));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+    /// The shelf part of the key equals the given text, ignoring case. The
+    /// prefix of n2 keys in a combined store is not part of the shelf.
+    Shelf(String),
+
+    /// The book part of the key equals the given text, ignoring case.
+    Book(String),
+
+    /// The shelf or book divider contains the given text, ignoring case.
+    Divider(String),
+
+    /// The material has data given by a dispersion formula.
+    Formula,
+
+    /// The material has tabulated data.
+    Tabulated,
+
+    /// The material has data for the imaginary part of the refractive index.
+    HasK,
+
+    /// The real part of the refractive index is defined over the whole range
+    /// of wavelengths in micrometers.
+    Covers([f64; 2]),
+
+    /// Every filter matches.
+    All(Vec<Filter>),
+
+    /// At least one filter matches.
+    Any(Vec<Filter>),
+
+    /// The filter does not match.
+    Not(Box<Filter>),
+}
+
+impl Filter {
+    /// Returns `true` if the item matches the filter.
+    ///
+    /// # Arguments
+    /// - `key`: The key of the item, e.g. glass:BK7:SCHOTT.
+    /// - `material`: The item.
+    pub fn matches(&self, key: &str, material: &Material) -> bool {
+        let mut parts = key_parts(key);
+        match self {
+            Filter::Shelf(shelf) => parts.next().is_some_and(|p| p.eq_ignore_ascii_case(shelf)),
+            Filter::Book(book) => parts.nth(1).is_some_and(|p| p.eq_ignore_ascii_case(book)),
+            Filter::Divider(text) => {
+                let text = text.to_lowercase();
+                [&material.shelf_divider, &material.book_divider]
+                    .into_iter()
+                    .flatten()
+                    .any(|divider| divider.to_lowercase().contains(&text))
+            }
+            Filter::Formula => material.data.iter().any(|data| data.is_formula()),
+            Filter::Tabulated => material.data.iter().any(|data| data.is_tabulated()),
+            Filter::HasK => material.data.iter().any(|data| {
+                matches!(
+                    data,
+                    DispersionData::TabulatedK { .. } | DispersionData::TabulatedNK { .. }
+                )
+            }),
+            Filter::Covers([min, max]) => material
+                .n_range()
+                .is_some_and(|[low, high]| low <= *min && *max <= high),
+            Filter::All(filters) => filters.iter().all(|f| f.matches(key, material)),
+            Filter::Any(filters) => filters.iter().any(|f| f.matches(key, material)),
+            Filter::Not(filter) => !filter.matches(key, material),
+        }
+    }
+
+    /// Returns a filter that matches when both filters match.
+    pub fn and(self, other: Filter) -> Filter {
+        match self {
+            Filter::All(mut filters) => {
+                filters.push(other);
+                Filter::All(filters)
+            }
+            filter => Filter::All(vec![filter, other]),
+        }
+    }
+
+    /// Returns a filter that matches when either filter matches.
+    pub fn or(self, other: Filter) -> Filter {
+        match self {
+            Filter::Any(mut filters) => {
+                filters.push(other);
+                Filter::Any(filters)
+            }
+            filter => Filter::Any(vec![filter, other]),
+        }
+    }
+
+    /// Returns a filter that matches when this filter does not.
+    #[allow(clippy::should_implement_trait)]
+    pub fn not(self) -> Filter {
+        Filter::Not(Box::new(self))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Store;
+
+    fn test_material(data: Vec<DispersionData>, divider: Option<&str>) -> Material {
+        Material {
+            data,
+            shelf_divider: divider.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    fn test_store() -> Store {
+        let mut store = Store::default();
+        store.insert(
+            "glass:BK7:SCHOTT".to_string(),
+            test_material(
+                vec![DispersionData::Formula2 {
+                    wavelength_range: [0.3, 2.5],
+                    c: vec![0.0; 7],
+                }],
+                Some("SCHOTT - optical glass"),
+            ),
+        );
+        store.insert(
+            "glass:F2:SCHOTT".to_string(),
+            test_material(
+                vec![DispersionData::Formula2 {
+                    wavelength_range: [0.5, 2.5],
+                    c: vec![0.0; 7],
+                }],
+                Some("SCHOTT - optical glass"),
+            ),
+        );
+        store.insert(
+            "main:Ag:Johnson".to_string(),
+            test_material(
+                vec![DispersionData::TabulatedNK {
+                    data: vec![[0.2, 0.1, 1.0], [1.0, 0.2, 6.0]],
+                }],
+                Some("Ag - Silver"),
+            ),
+        );
+        store.insert(
+            "main:Ag:Thin".to_string(),
+            test_material(
+                vec![DispersionData::TabulatedK {
+                    data: vec![[0.2, 1.0], [1.0, 6.0]],
+                }],
+                Some("Ag - Silver"),
+            ),
+        );
+        store
+    }
+
+    fn retained(filter: &Filter) -> Vec<String> {
+        let mut store = test_store();
+        store.retain(|key, material| filter.matches(key, material));
+        let mut keys: Vec<String> = store.keys().cloned().collect();
+        keys.sort();
+        keys
+    }
+
+    #[test]
+    fn test_key_filters() {
+        assert_eq!(
+            retained(&Filter::Shelf("GLASS".to_string())),
+            ["glass:BK7:SCHOTT", "glass:F2:SCHOTT"]
+        );
+        assert_eq!(
+            retained(&Filter::Book("bk7".to_string())),
+            ["glass:BK7:SCHOTT"]
+        );
+        assert_eq!(
+            retained(&Filter::Divider("silver".to_string())),
+            ["main:Ag:Johnson", "main:Ag:Thin"]
+        );
+    }
+
+    #[test]
+    fn test_key_filters_in_combined_store() {
+        let mut n2 = Store::default();
+        n2.insert("glass:BK7:Milam".to_string(), test_material(vec![], None));
+        let mut store = Store::combine(test_store(), n2);
+        let filter = Filter::Shelf("glass".to_string()).and(Filter::Book("BK7".to_string()));
+        store.retain(|key, material| filter.matches(key, material));
+        let mut keys: Vec<&String> = store.keys().collect();
+        keys.sort();
+        assert_eq!(keys, ["glass:BK7:SCHOTT", "n2:glass:BK7:Milam"]);
+    }
+
+    #[test]
+    fn test_data_filters() {
+        assert_eq!(
+            retained(&Filter::Formula),
+            ["glass:BK7:SCHOTT", "glass:F2:SCHOTT"]
+        );
+        assert_eq!(
+            retained(&Filter::Tabulated),
+            ["main:Ag:Johnson", "main:Ag:Thin"]
+        );
+        assert_eq!(retained(&Filter::HasK), ["main:Ag:Johnson", "main:Ag:Thin"]);
+    }
+
+    #[test]
+    fn test_covers() {
+        // Materials without real data cover nothing
+        assert_eq!(
+            retained(&Filter::Covers([0.4, 0.7])),
+            ["glass:BK7:SCHOTT", "main:Ag:Johnson"]
+        );
+    }
+
+    #[test]
+    fn test_combinators() {
+        let filter = Filter::Shelf("glass".to_string()).and(Filter::Covers([0.4, 0.7]));
+        assert_eq!(retained(&filter), ["glass:BK7:SCHOTT"]);
+
+        let filter = Filter::Book("BK7".to_string()).or(Filter::HasK.and(Filter::Formula.not()));
+        assert_eq!(
+            retained(&filter),
+            ["glass:BK7:SCHOTT", "main:Ag:Johnson", "main:Ag:Thin"]
+        );
+
+        assert_eq!(retained(&Filter::All(vec![])).len(), 4);
+        assert!(retained(&Filter::Any(vec![])).is_empty());
+    }
+}
@@ -0,0 +1,216 @@
+//! Finding materials by their optical properties.
+use crate::internal::store::{Material, Store};
+
+/// An optical property that a material can be constrained by.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Property {
+    /// The real part of the refractive index at a wavelength in micrometers.
+    N(f64),
+
+    /// The imaginary part of the refractive index at a wavelength in
+    /// micrometers. Materials without imaginary data have a k of 0.
+    K(f64),
+
+    /// The derivative of n with respect to wavelength at a wavelength in
+    /// micrometers.
+    DnDLambda(f64),
+
+    /// The Abbe number Vd.
+    AbbeNumber,
+}
+
+impl Property {
+    /// Evaluates the property of a material.
+    ///
+    /// # Returns
+    /// The value of the property, or `None` if the material's data do not
+    /// cover the wavelengths needed to compute it.
+    pub fn evaluate(&self, material: &Material) -> Option<f64> {
+        let value = match *self {
+            Property::N(wavelength) => material.n(wavelength).ok(),
+            Property::K(wavelength) => material.k(wavelength).ok().map(|k| k.unwrap_or(0.0)),
+            Property::DnDLambda(wavelength) => material.dn_dlambda(wavelength).ok(),
+            Property::AbbeNumber => material.abbe_number().ok(),
+        };
+        value.filter(|value| value.is_finite())
+    }
+}
+
+/// A target value for a property and how far from it a material may be.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Constraint {
+    pub property: Property,
+    pub target: f64,
+    pub tolerance: f64,
+}
+
+impl Store {
+    /// Finds the materials that meet every constraint.
+    ///
+    /// Materials whose data do not cover the wavelengths of a constraint are
+    /// skipped.
+    ///
+    /// # Arguments
+    /// - `constraints`: The properties to match.
+    ///
+    /// # Returns
+    /// The distance and the key of each matching material, closest first.
+    /// The distance is the root mean square of each property's difference
+    /// from its target divided by its tolerance, so it is between 0 and 1.
+    /// Materials with equal distances are ordered by key.
+    pub fn find(&self, constraints: &[Constraint]) -> Vec<(f64, &str)> {
+        if constraints.is_empty() {
+            return Vec::new();
+        }
+
+        let mut results: Vec<(f64, &str)> = self
+            .iter()
+            .filter_map(|(key, material)| {
+                let mut sum = 0.0;
+                for constraint in constraints {
+                    let value = constraint.property.evaluate(material)?;
+                    let difference = (value - constraint.target).abs();
+                    if difference > constraint.tolerance {
+                        return None;
+                    }
+                    if constraint.tolerance > 0.0 {
+                        sum += (difference / constraint.tolerance).powi(2);
+                    }
+                }
+                Some(((sum / constraints.len() as f64).sqrt(), key.as_str()))
+            })
+            .collect();
+        results.sort_by(|a, b| a.0.total_cmp(&b.0).then_with(|| a.1.cmp(b.1)));
+        results
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::internal::optics::D_LINE;
+    use crate::DispersionData;
+
+    fn formula_glass(c: Vec<f64>) -> Material {
+        Material {
+            shelf: "glass".to_string(),
+            data: vec![DispersionData::Formula2 {
+                wavelength_range: [0.37, 2.5],
+                c,
+            }],
+            ..Default::default()
+        }
+    }
+
+    fn test_store() -> Store {
+        let mut store = Store::default();
+        store.insert(
+            "glass:BK7:SCHOTT".to_string(),
+            formula_glass(vec![
+                0.0,
+                1.03961212,
+                0.00600069867,
+                0.231792344,
+                0.0200179144,
+                1.01046945,
+                103.560653,
+            ]),
+        );
+        store.insert(
+            "glass:SF11:SCHOTT".to_string(),
+            formula_glass(vec![
+                0.0,
+                1.73759695,
+                0.013188707,
+                0.313747346,
+                0.0623068142,
+                1.89878101,
+                155.23629,
+            ]),
+        );
+        store.insert(
+            "main:Ag:Johnson".to_string(),
+            Material {
+                data: vec![DispersionData::TabulatedNK {
+                    data: vec![[0.4, 0.05, 2.1], [0.7, 0.14, 4.5]],
+                }],
+                ..formula_glass(vec![])
+            },
+        );
+        store
+    }
+
+    #[test]
+    fn test_find_by_n() {
+        let store = test_store();
+        let results = store.find(&[Constraint {
+            property: Property::N(D_LINE),
+            target: 1.52,
+            tolerance: 0.01,
+        }]);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].1, "glass:BK7:SCHOTT");
+        assert!(results[0].0 < 1.0);
+    }
+
+    #[test]
+    fn test_find_ranks_by_distance() {
+        let store = test_store();
+        let results = store.find(&[Constraint {
+            property: Property::N(D_LINE),
+            target: 1.6,
+            tolerance: 0.3,
+        }]);
+        let keys: Vec<&str> = results.iter().map(|(_, key)| *key).collect();
+        assert_eq!(keys, ["glass:BK7:SCHOTT", "glass:SF11:SCHOTT"]);
+    }
+
+    #[test]
+    fn test_find_skips_uncovered_wavelengths() {
+        let store = test_store();
+        let results = store.find(&[Constraint {
+            property: Property::N(0.3),
+            target: 1.5,
+            tolerance: 10.0,
+        }]);
+        assert!(results.is_empty());
+    }
+
+    #[test]
+    fn test_find_by_abbe_number_and_k() {
+        let store = test_store();
+        let results = store.find(&[
+            Constraint {
+                property: Property::AbbeNumber,
+                target: 64.0,
+                tolerance: 1.0,
+            },
+            Constraint {
+                property: Property::K(D_LINE),
+                target: 0.0,
+                tolerance: 1e-6,
+            },
+        ]);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].1, "glass:BK7:SCHOTT");
+
+        let results = store.find(&[Constraint {
+            property: Property::K(0.5),
+            target: 3.0,
+            tolerance: 0.5,
+        }]);
+        assert_eq!(results[0].1, "main:Ag:Johnson");
+    }
+
+    #[test]
+    fn test_find_by_dn_dlambda() {
+        let store = test_store();
+        let results = store.find(&[Constraint {
+            property: Property::DnDLambda(D_LINE),
+            target: -0.15,
+            tolerance: 0.05,
+        }]);
+        let keys: Vec<&str> = results.iter().map(|(_, key)| *key).collect();
+        assert_eq!(keys, ["glass:SF11:SCHOTT"]);
+    }
+}
@@ -0,0 +1,213 @@
+//! Glass maps (Abbe diagrams) of nd against Vd.
+use std::fmt::Write;
+
+use crate::internal::store::Store;
+use crate::internal::svg::{
+    padded_range, Scale, Svg, HEIGHT, MARGIN_BOTTOM, MARGIN_LEFT, MARGIN_RIGHT, MARGIN_TOP,
+    PALETTE, WIDTH,
+};
+
+/// A material on a glass map.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GlassMapPoint<'a> {
+    pub key: &'a str,
+    pub book: &'a str,
+    pub nd: f64,
+    pub vd: f64,
+}
+
+/// The refractive indices and Abbe numbers of a set of materials.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct GlassMap<'a> {
+    /// The points of the map, sorted by key.
+    pub points: Vec<GlassMapPoint<'a>>,
+}
+
+impl Store {
+    /// Computes the glass map of the store.
+    ///
+    /// Materials whose data do not cover the d, F and C lines are left out.
+    pub fn glass_map(&self) -> GlassMap<'_> {
+        let mut points: Vec<GlassMapPoint> = self
+            .iter()
+            .filter_map(|(key, material)| {
+                let nd = material.nd().ok().filter(|nd| nd.is_finite())?;
+                let vd = material.abbe_number().ok().filter(|vd| vd.is_finite())?;
+                Some(GlassMapPoint {
+                    key,
+                    book: &material.book,
+                    nd,
+                    vd,
+                })
+            })
+            .collect();
+        points.sort_by(|a, b| a.key.cmp(b.key));
+        GlassMap { points }
+    }
+}
+
+impl GlassMap<'_> {
+    /// Writes the map as CSV with the columns key, book, nd and vd.
+    pub fn to_csv(&self) -> String {
+        let quote = |text: &str| format!("\"{}\"", text.replace('"', "\"\""));
+        let mut csv = String::from("key,book,nd,vd\n");
+        for point in &self.points {
+            let _ = writeln!(
+                csv,
+                "{},{},{},{}",
+                quote(point.key),
+                quote(point.book),
+                point.nd,
+                point.vd
+            );
+        }
+        csv
+    }
+
+    /// Draws the map as a self-contained SVG scatter plot.
+    ///
+    /// As is customary, Vd decreases from left to right. Each point is
+    /// labelled with its book and shows its key when hovered.
+    ///
+    /// # Arguments
+    /// - `highlight`: The keys of materials to draw larger and in a different
+    ///   colour.
+    pub fn to_svg(&self, highlight: &[&str]) -> String {
+        let (vd_min, vd_max) = padded_range(self.points.iter().map(|p| p.vd));
+        let (nd_min, nd_max) = padded_range(self.points.iter().map(|p| p.nd));
+        let x = Scale::new(vd_min, vd_max, WIDTH - MARGIN_RIGHT, MARGIN_LEFT);
+        let y = Scale::new(nd_min, nd_max, HEIGHT - MARGIN_BOTTOM, MARGIN_TOP);
+
+        let mut svg = Svg::new();
+        svg.axes(&x, &y, "Abbe number Vd", "Refractive index nd");
+
+        // Highlighted points are drawn last so that they are on top
+        let (highlighted, others): (Vec<_>, Vec<_>) = self
+            .points
+            .iter()
+            .partition(|point| highlight.contains(&point.key));
+        for (points, color, radius) in [(others, PALETTE[0], 3.0), (highlighted, PALETTE[1], 5.0)] {
+            for point in points {
+                let (px, py) = (x.map(point.vd), y.map(point.nd));
+                let title = format!("{} (nd = {:.5}, Vd = {:.2})", point.key, point.nd, point.vd);
+                svg.circle(px, py, radius, color, Some(&title));
+                svg.text(
+                    px + radius + 2.0,
+                    py - radius,
+                    "start",
+                    point.book,
+                    Some(color),
+                );
+            }
+        }
+
+        svg.finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use approx::assert_abs_diff_eq;
+
+    use super::*;
+    use crate::{DispersionData, Material};
+
+    fn test_material(book: &str, data: DispersionData) -> Material {
+        Material {
+            shelf: "glass".to_string(),
+            book: book.to_string(),
+            page: "SCHOTT".to_string(),
+            data: vec![data],
+            ..Default::default()
+        }
+    }
+
+    fn test_store() -> Store {
+        let mut store = Store::default();
+        store.insert(
+            "glass:BK7:SCHOTT".to_string(),
+            test_material(
+                "N-BK7",
+                DispersionData::Formula2 {
+                    wavelength_range: [0.3, 2.5],
+                    c: vec![
+                        0.0,
+                        1.03961212,
+                        0.00600069867,
+                        0.231792344,
+                        0.0200179144,
+                        1.01046945,
+                        103.560653,
+                    ],
+                },
+            ),
+        );
+        store.insert(
+            "glass:SF11:SCHOTT".to_string(),
+            test_material(
+                "N-SF11",
+                DispersionData::Formula2 {
+                    wavelength_range: [0.37, 2.5],
+                    c: vec![
+                        0.0,
+                        1.73759695,
+                        0.013188707,
+                        0.313747346,
+                        0.0623068142,
+                        1.89878101,
+                        155.23629,
+                    ],
+                },
+            ),
+        );
+        // The data do not cover the F line
+        store.insert(
+            "main:Ag:Johnson".to_string(),
+            test_material(
+                "Ag (Silver)",
+                DispersionData::TabulatedNK {
+                    data: vec![[0.5, 0.05, 3.1], [0.7, 0.14, 4.5]],
+                },
+            ),
+        );
+        store
+    }
+
+    #[test]
+    fn test_glass_map_points() {
+        let store = test_store();
+        let map = store.glass_map();
+        let keys: Vec<&str> = map.points.iter().map(|p| p.key).collect();
+        assert_eq!(keys, ["glass:BK7:SCHOTT", "glass:SF11:SCHOTT"]);
+        assert_abs_diff_eq!(map.points[0].nd, 1.5168, epsilon = 1e-4);
+        assert_abs_diff_eq!(map.points[1].vd, 25.68, epsilon = 0.05);
+    }
+
+    #[test]
+    fn test_glass_map_csv() {
+        let store = test_store();
+        let csv = store.glass_map().to_csv();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], "key,book,nd,vd");
+        assert!(lines[1].starts_with("\"glass:BK7:SCHOTT\",\"N-BK7\",1.5168"));
+    }
+
+    #[test]
+    fn test_glass_map_svg() {
+        let store = test_store();
+        let svg = store.glass_map().to_svg(&["glass:SF11:SCHOTT"]);
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert!(svg.trim_end().ends_with("</svg>"));
+        assert_eq!(svg.matches("<circle").count(), 2);
+        assert!(svg.contains(&format!("r=\"5\" fill=\"{}\"", PALETTE[1])));
+        assert!(svg.contains(">N-BK7</text>"));
+    }
+
+    #[test]
+    fn test_empty_glass_map_svg() {
+        let svg = GlassMap::default().to_svg(&[]);
+        assert!(svg.contains("Abbe number Vd"));
+        assert!(!svg.contains("<circle"));
+    }
+}
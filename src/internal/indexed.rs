@@ -0,0 +1,351 @@
+//! An indexed binary store that decodes materials on demand.
+//!
+//! Deserializing a whole store allocates every material's data up front. An
+//! indexed store instead prefixes the materials with a table of offsets so that
+//! a single material can be located with a binary search and decoded on its
+//! own. The store can be read directly from a memory-mapped file, making
+//! opening it practically free.
+//!
+//! All integers are little-endian. The layout of the file is:
+//!
+//! | Section | Contents                                                  |
+//! |---------|-----------------------------------------------------------|
+//! | Header  | `RIAIDX`, the format version (`u16`), entry count (`u64`) |
+//! | Index   | One entry per material, sorted by key: the key offset,    |
+//! |         | key length, material offset, and material length (`u64`)  |
+//! | Keys    | The UTF-8 keys                                            |
+//! | Values  | Each material serialized on its own with bitcode          |
+//!
+//! Offsets are relative to the start of the file.
+use std::ops::Range;
+
+use anyhow::{anyhow, Result};
+
+use crate::internal::store::{Material, Store};
+
+/// The first bytes of every indexed store.
+const MAGIC: &[u8; 6] = b"RIAIDX";
+
+/// The version of the indexed layout written by this library. It must be
+/// incremented whenever the layout of [`Material`] changes.
+const FORMAT_VERSION: u16 = 1;
+
+const HEADER_LEN: usize = 16;
+const ENTRY_LEN: usize = 32;
+
+/// A read-only store whose materials are decoded only when requested.
+///
+/// The store borrows its data from any byte buffer, such as a `Vec<u8>` or a
+/// memory-mapped file.
+pub struct IndexedStore<B: AsRef<[u8]>> {
+    data: B,
+    len: usize,
+}
+
+impl<B: AsRef<[u8]>> IndexedStore<B> {
+    /// Creates an indexed store from its serialized bytes.
+    ///
+    /// The header and index are checked, but the materials are not decoded.
+    ///
+    /// # Arguments
+    /// - `data`: The serialized store.
+    ///
+    /// # Errors
+    /// - If the data are not an indexed store or were written with an
+    ///   unsupported format version.
+    /// - If any entry of the index points outside of the data.
+    /// - If the keys of the index are not sorted and unique.
+    pub fn from_bytes(data: B) -> Result<Self> {
+        let bytes = data.as_ref();
+        if bytes.len() < HEADER_LEN || &bytes[..MAGIC.len()] != MAGIC {
+            return Err(anyhow!("The data are not an indexed store."));
+        }
+
+        let version = u16::from_le_bytes([bytes[6], bytes[7]]);
+        if version != FORMAT_VERSION {
+            return Err(anyhow!(
+                "Unsupported indexed store version {}; expected {}",
+                version,
+                FORMAT_VERSION
+            ));
+        }
+
+        let len = usize::try_from(read_u64(bytes, 8))?;
+        let index_end = len
+            .checked_mul(ENTRY_LEN)
+            .and_then(|index_len| index_len.checked_add(HEADER_LEN))
+            .filter(|&end| end <= bytes.len())
+            .ok_or(anyhow!("The index of the store is truncated."))?;
+
+        let store = IndexedStore { data, len };
+        let bytes = store.data.as_ref();
+        let mut previous: Option<&[u8]> = None;
+        for i in 0..len {
+            let (key, value) = store.entry(i);
+            if key.start < index_end || key.end > bytes.len() || value.end > bytes.len() {
+                return Err(anyhow!("Entry {} of the index is out of bounds.", i));
+            }
+            let key = &bytes[key];
+            std::str::from_utf8(key)?;
+            // The binary search in `get` relies on the order of the keys.
+            if previous.is_some_and(|previous| previous >= key) {
+                return Err(anyhow!(
+                    "The keys of the index are not sorted and unique at entry {}.",
+                    i
+                ));
+            }
+            previous = Some(key);
+        }
+
+        Ok(store)
+    }
+
+    /// Returns the item from the store associated with the given key.
+    ///
+    /// # Arguments
+    /// - `key`: The key to look up in the store.
+    ///
+    /// # Returns
+    /// The item associated with the given key, if it exists.
+    ///
+    /// # Errors
+    /// - If the item cannot be decoded.
+    pub fn get(&self, key: &str) -> Result<Option<Material>> {
+        let bytes = self.data.as_ref();
+        let mut lo = 0;
+        let mut hi = self.len;
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            let (key_range, value_range) = self.entry(mid);
+            match bytes[key_range].cmp(key.as_bytes()) {
+                std::cmp::Ordering::Less => lo = mid + 1,
+                std::cmp::Ordering::Greater => hi = mid,
+                std::cmp::Ordering::Equal => {
+                    return Ok(Some(bitcode::deserialize(&bytes[value_range])?));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Returns an iterator over the keys in the store in sorted order.
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        let bytes = self.data.as_ref();
+        (0..self.len).map(move |i| {
+            let (key, _) = self.entry(i);
+            // Keys were checked to be valid UTF-8 when the store was created.
+            std::str::from_utf8(&bytes[key]).unwrap_or_default()
+        })
+    }
+
+    /// Returns the number of items in the store.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the store contains no items.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Decodes every item into an in-memory store.
+    ///
+    /// # Errors
+    /// - If any item cannot be decoded.
+    pub fn to_store(&self) -> Result<Store> {
+        self.select(|_| true)
+    }
+
+    /// Decodes the items whose keys satisfy a predicate into an in-memory
+    /// store. The other items are not decoded.
+    ///
+    /// # Arguments
+    /// - `predicate`: Returns `true` for the keys of the items to decode.
+    ///
+    /// # Errors
+    /// - If any selected item cannot be decoded.
+    pub fn select(&self, predicate: impl Fn(&str) -> bool) -> Result<Store> {
+        let bytes = self.data.as_ref();
+        let mut store = Store::default();
+        for i in 0..self.len {
+            let (key, value) = self.entry(i);
+            let key = std::str::from_utf8(&bytes[key])?;
+            if predicate(key) {
+                store.insert(key.to_string(), bitcode::deserialize(&bytes[value])?);
+            }
+        }
+        Ok(store)
+    }
+
+    /// Returns the byte ranges of the key and the value of the i'th entry.
+    fn entry(&self, i: usize) -> (Range<usize>, Range<usize>) {
+        let bytes = self.data.as_ref();
+        let offset = HEADER_LEN + i * ENTRY_LEN;
+        let range = |start: u64, len: u64| {
+            let start = start as usize;
+            start..start.saturating_add(len as usize)
+        };
+        (
+            range(read_u64(bytes, offset), read_u64(bytes, offset + 8)),
+            range(read_u64(bytes, offset + 16), read_u64(bytes, offset + 24)),
+        )
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl IndexedStore<memmap2::Mmap> {
+    /// Opens an indexed store file by memory-mapping it.
+    ///
+    /// # Arguments
+    /// - `path`: The path to the store file.
+    ///
+    /// # Errors
+    /// - If the file cannot be opened or mapped.
+    /// - If the file is not a valid indexed store.
+    pub fn open(path: impl AsRef<std::path::Path>) -> Result<Self> {
+        let file = std::fs::File::open(path)?;
+        // SAFETY: The map is read-only. As with any memory-mapped file, the
+        // contents are undefined if the file is modified while it is mapped.
+        let mmap = unsafe { memmap2::Mmap::map(&file)? };
+        IndexedStore::from_bytes(mmap)
+    }
+}
+
+impl Store {
+    /// Serializes the store in the indexed format.
+    ///
+    /// # Errors
+    /// - If any item cannot be serialized.
+    pub fn to_indexed(&self) -> Result<Vec<u8>> {
+        let mut keys: Vec<&String> = self.keys().collect();
+        keys.sort();
+
+        let values = keys
+            .iter()
+            .map(|key| Ok(bitcode::serialize(self.get(key).unwrap())?))
+            .collect::<Result<Vec<Vec<u8>>>>()?;
+
+        let mut data = Vec::new();
+        data.extend_from_slice(MAGIC);
+        data.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        data.extend_from_slice(&(keys.len() as u64).to_le_bytes());
+
+        let mut key_offset = HEADER_LEN + keys.len() * ENTRY_LEN;
+        let mut value_offset = key_offset + keys.iter().map(|key| key.len()).sum::<usize>();
+        for (key, value) in keys.iter().zip(&values) {
+            for n in [key_offset, key.len(), value_offset, value.len()] {
+                data.extend_from_slice(&(n as u64).to_le_bytes());
+            }
+            key_offset += key.len();
+            value_offset += value.len();
+        }
+
+        for key in &keys {
+            data.extend_from_slice(key.as_bytes());
+        }
+        for value in &values {
+            data.extend_from_slice(value);
+        }
+
+        Ok(data)
+    }
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> u64 {
+    let mut buf = [0; 8];
+    buf.copy_from_slice(&bytes[offset..offset + 8]);
+    u64::from_le_bytes(buf)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::DispersionData;
+
+    fn test_material(page: &str) -> Material {
+        Material {
+            shelf: "main".to_string(),
+            book: "Ag (Silver)".to_string(),
+            page: page.to_string(),
+            data: vec![DispersionData::TabulatedK {
+                data: vec![[0.5, 0.1], [0.6, 0.2]],
+            }],
+            ..Default::default()
+        }
+    }
+
+    fn test_store() -> Store {
+        let mut store = Store::default();
+        store.insert("main:Ag:Johnson".to_string(), test_material("Johnson"));
+        store.insert("main:Ag:Choi".to_string(), test_material("Choi"));
+        store.insert("glass:BK7:SCHOTT".to_string(), test_material("SCHOTT"));
+        store
+    }
+
+    #[test]
+    fn test_indexed_get() {
+        let indexed = IndexedStore::from_bytes(test_store().to_indexed().unwrap()).unwrap();
+        assert_eq!(indexed.len(), 3);
+        let material = indexed.get("main:Ag:Choi").unwrap().unwrap();
+        assert_eq!(material.page, "Choi");
+        assert_eq!(material.data.len(), 1);
+        assert!(indexed.get("main:Ag:Missing").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_indexed_keys_are_sorted() {
+        let indexed = IndexedStore::from_bytes(test_store().to_indexed().unwrap()).unwrap();
+        let keys: Vec<&str> = indexed.keys().collect();
+        assert_eq!(
+            keys,
+            ["glass:BK7:SCHOTT", "main:Ag:Choi", "main:Ag:Johnson"]
+        );
+    }
+
+    #[test]
+    fn test_indexed_to_store() {
+        let indexed = IndexedStore::from_bytes(test_store().to_indexed().unwrap()).unwrap();
+        let store = indexed.to_store().unwrap();
+        assert_eq!(store.get("main:Ag:Johnson").unwrap().page, "Johnson");
+    }
+
+    #[test]
+    fn test_indexed_empty_store() {
+        let indexed = IndexedStore::from_bytes(Store::default().to_indexed().unwrap()).unwrap();
+        assert!(indexed.is_empty());
+        assert!(indexed.get("main:Ag:Johnson").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_indexed_rejects_bad_magic() {
+        assert!(IndexedStore::from_bytes(b"{\"inner\":{}}".to_vec()).is_err());
+    }
+
+    #[test]
+    fn test_indexed_rejects_truncated_data() {
+        let data = test_store().to_indexed().unwrap();
+        assert!(IndexedStore::from_bytes(&data[..data.len() - 1]).is_err());
+        assert!(IndexedStore::from_bytes(&data[..HEADER_LEN + 4]).is_err());
+    }
+
+    #[test]
+    fn test_indexed_rejects_unsorted_keys() {
+        let mut data = test_store().to_indexed().unwrap();
+        // Swap the first two entries of the index
+        let first = HEADER_LEN..HEADER_LEN + ENTRY_LEN;
+        let entry = data[first.clone()].to_vec();
+        data.copy_within(first.end..first.end + ENTRY_LEN, first.start);
+        data[first.end..first.end + ENTRY_LEN].copy_from_slice(&entry);
+        let err = IndexedStore::from_bytes(data).err().unwrap();
+        assert!(err.to_string().contains("not sorted and unique"));
+    }
+
+    #[test]
+    fn test_indexed_rejects_duplicate_keys() {
+        let mut data = test_store().to_indexed().unwrap();
+        // Point the second entry at the key of the first
+        let second = HEADER_LEN + ENTRY_LEN;
+        data.copy_within(HEADER_LEN..HEADER_LEN + 16, second);
+        assert!(IndexedStore::from_bytes(data).is_err());
+    }
+}
@@ -0,0 +1,404 @@
+//! Reading and writing serialized stores.
+use std::fmt;
+#[cfg(feature = "json")]
+use std::io::{Read, Write};
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+
+use crate::internal::compression::{decompress, Compression};
+#[cfg(feature = "bitcode")]
+use crate::internal::indexed::IndexedStore;
+use crate::internal::store::Store;
+
+/// The first bytes of the indexed and compact stores. These are duplicated
+/// here so that the formats can be detected without the `bitcode` feature.
+const INDEXED_MAGIC: &[u8] = b"RIAIDX";
+const COMPACT_MAGIC: &[u8] = b"RIACMP";
+
+/// The serialization formats of a store file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StoreFormat {
+    Json,
+    Bitcode,
+
+    /// bitcode-encoded materials behind an index; see [`crate::IndexedStore`].
+    Indexed,
+
+    /// bitcode with interned strings and columnar tables.
+    Compact,
+}
+
+impl StoreFormat {
+    /// Detects the format of a serialized store from its leading bytes.
+    ///
+    /// bitcode has no magic number, so it is never detected.
+    ///
+    /// # Arguments
+    /// - `data`: The serialized store, or at least its first few bytes.
+    ///
+    /// # Returns
+    /// The format, or `None` if the data are neither an indexed nor a compact
+    /// store and do not look like a JSON object.
+    pub fn detect(data: &[u8]) -> Option<StoreFormat> {
+        if data.starts_with(INDEXED_MAGIC) {
+            return Some(StoreFormat::Indexed);
+        }
+        if data.starts_with(COMPACT_MAGIC) {
+            return Some(StoreFormat::Compact);
+        }
+
+        // A JSON store is an object whose first token is either a key or the
+        // closing brace.
+        let mut tokens = data.iter().filter(|b| !b.is_ascii_whitespace());
+        match (tokens.next(), tokens.next()) {
+            (Some(b'{'), Some(b'"' | b'}')) => Some(StoreFormat::Json),
+            _ => None,
+        }
+    }
+
+    /// Checks that data whose format was not recognized may be bitcode, the
+    /// only format without a magic number.
+    ///
+    /// # Arguments
+    /// - `data`: The decompressed store, or at least its first few bytes.
+    ///
+    /// # Errors
+    /// - If the data begin with the magic number of a compression format, which
+    ///   would have been removed by decompressing them.
+    pub fn assume_bitcode(data: &[u8]) -> Result<StoreFormat> {
+        match Compression::detect(data) {
+            Some(compression) => Err(anyhow!(
+                "Unrecognized store format: the data are still {} compressed after decompressing them",
+                compression
+            )),
+            None => Ok(StoreFormat::Bitcode),
+        }
+    }
+
+    /// Guesses the format of a store file from its extension. A trailing
+    /// compression extension, such as `.gz` or `.zst`, is skipped.
+    ///
+    /// # Returns
+    /// The format, or `None` if the extension does not name one, as with the
+    /// default `.dat`.
+    pub fn from_extension(path: &Path) -> Option<StoreFormat> {
+        let mut path = path;
+        let mut extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        if matches!(extension.as_str(), "gz" | "gzip" | "zst" | "zstd") {
+            path = Path::new(path.file_stem()?);
+            extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        }
+        match extension.as_str() {
+            "json" => Some(StoreFormat::Json),
+            "bitcode" | "bc" => Some(StoreFormat::Bitcode),
+            "idx" | "mmap" => Some(StoreFormat::Indexed),
+            "cmp" | "compact" => Some(StoreFormat::Compact),
+            _ => None,
+        }
+    }
+
+    /// Detects the format of a store file from its contents, and from its
+    /// extension when the contents are not conclusive.
+    ///
+    /// Indexed, compact and JSON stores are recognized from their leading
+    /// bytes. bitcode has no magic number, so it is only detected from the
+    /// extension.
+    ///
+    /// # Arguments
+    /// - `data`: The decompressed store, or at least its first few bytes.
+    /// - `path`: The path of the store file.
+    ///
+    /// # Returns
+    /// The format, or `None` if neither the contents nor the extension name
+    /// one.
+    pub fn detect_with_extension(data: &[u8], path: &Path) -> Option<StoreFormat> {
+        StoreFormat::detect(data).or_else(|| StoreFormat::from_extension(path))
+    }
+
+    /// The cargo feature required to read this format.
+    fn feature(&self) -> &'static str {
+        match self {
+            StoreFormat::Json => "json",
+            StoreFormat::Bitcode | StoreFormat::Indexed | StoreFormat::Compact => "bitcode",
+        }
+    }
+}
+
+impl fmt::Display for StoreFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StoreFormat::Json => write!(f, "json"),
+            StoreFormat::Bitcode => write!(f, "bitcode"),
+            StoreFormat::Indexed => write!(f, "indexed"),
+            StoreFormat::Compact => write!(f, "compact"),
+        }
+    }
+}
+
+impl Store {
+    /// Reads a store in any supported format, detecting the format and any
+    /// compression from the data. Data in no recognized format are read as
+    /// bitcode.
+    ///
+    /// Stores written by older versions of ria are upgraded to the current
+    /// layout.
+    ///
+    /// # Arguments
+    /// - `data`: The serialized store.
+    ///
+    /// # Errors
+    /// - If the detected format or compression was not enabled when this crate
+    ///   was compiled.
+    /// - If the data cannot be decompressed or deserialized.
+    /// - If the format of the data is not recognized and they cannot be read as
+    ///   bitcode.
+    pub fn from_bytes(data: &[u8]) -> Result<Store> {
+        let data = decompress(data)?;
+        let data = data.as_ref();
+        let format = match StoreFormat::detect(data) {
+            Some(format) => format,
+            None => {
+                StoreFormat::assume_bitcode(data)?;
+                return Store::from_unrecognized_bytes(data);
+            }
+        };
+        match format {
+            #[cfg(feature = "json")]
+            StoreFormat::Json => Ok(Store::migrate_from_json(data)?.0),
+            #[cfg(feature = "bitcode")]
+            StoreFormat::Bitcode => Store::from_bitcode_bytes(data),
+            #[cfg(feature = "bitcode")]
+            StoreFormat::Indexed => IndexedStore::from_bytes(data)?.to_store(),
+            #[cfg(feature = "bitcode")]
+            StoreFormat::Compact => Store::from_compact_bytes(data),
+            #[allow(unreachable_patterns)]
+            format => Err(anyhow!(
+                "Reading {} stores requires the `{}` feature",
+                format,
+                format.feature()
+            )),
+        }
+    }
+
+    /// Reads data in no recognized format as a bitcode store.
+    #[cfg_attr(not(feature = "bitcode"), allow(unused_variables))]
+    fn from_unrecognized_bytes(data: &[u8]) -> Result<Store> {
+        #[cfg(feature = "bitcode")]
+        return Store::from_bitcode_bytes(data).map_err(|e| {
+            anyhow!(
+                "Unrecognized store format: the data are not a JSON, indexed or compact store and cannot be read as bitcode: {}",
+                e
+            )
+        });
+        #[cfg(not(feature = "bitcode"))]
+        Err(anyhow!(
+            "Unrecognized store format: the data are not a JSON, indexed or compact store, and reading bitcode stores requires the `bitcode` feature"
+        ))
+    }
+
+    /// Reads a JSON store.
+    ///
+    /// Stores written by older versions of ria are upgraded to the current
+    /// layout.
+    ///
+    /// # Arguments
+    /// - `reader`: The source of the JSON data.
+    ///
+    /// # Errors
+    /// - If the data cannot be read or deserialized.
+    #[cfg(feature = "json")]
+    pub fn from_json_reader(mut reader: impl Read) -> Result<Store> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        Ok(Store::migrate_from_json(&data)?.0)
+    }
+
+    /// Writes the store as JSON.
+    ///
+    /// # Arguments
+    /// - `writer`: The destination of the JSON data.
+    #[cfg(feature = "json")]
+    pub fn to_json_writer(&self, writer: impl Write) -> Result<()> {
+        serde_json::to_writer(writer, self)?;
+        Ok(())
+    }
+
+    /// Reads a bitcode store.
+    ///
+    /// Stores written by older versions of ria are upgraded to the current
+    /// layout.
+    ///
+    /// # Arguments
+    /// - `data`: The bitcode data.
+    ///
+    /// # Errors
+    /// - If the data cannot be deserialized.
+    #[cfg(feature = "bitcode")]
+    pub fn from_bitcode_bytes(data: &[u8]) -> Result<Store> {
+        Ok(Store::migrate_from_bitcode(data)?.0)
+    }
+
+    /// Serializes the store with bitcode.
+    #[cfg(feature = "bitcode")]
+    pub fn to_bitcode(&self) -> Result<Vec<u8>> {
+        Ok(bitcode::serialize(self)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[cfg(any(feature = "json", feature = "bitcode"))]
+    use crate::Material;
+
+    #[cfg(any(feature = "json", feature = "bitcode"))]
+    fn test_store() -> Store {
+        let mut store = Store::default();
+        store.insert(
+            "main:Ag:Johnson".to_string(),
+            Material {
+                shelf: "main".to_string(),
+                book: "Ag (Silver)".to_string(),
+                page: "Johnson and Christy 1972".to_string(),
+                ..Default::default()
+            },
+        );
+        store
+    }
+
+    #[test]
+    fn test_detect_json() {
+        let json = Some(StoreFormat::Json);
+        assert_eq!(StoreFormat::detect(b"{\"inner\":{}}"), json);
+        assert_eq!(StoreFormat::detect(b"\n  { \"inner\""), json);
+        assert_eq!(StoreFormat::detect(b"{}"), json);
+    }
+
+    #[test]
+    fn test_detect_unrecognized() {
+        assert_eq!(StoreFormat::detect(&[0]), None);
+        assert_eq!(StoreFormat::detect(b"{\x01\x02"), None);
+        assert_eq!(StoreFormat::detect(&[]), None);
+    }
+
+    #[test]
+    fn test_assume_bitcode() {
+        assert_eq!(
+            StoreFormat::assume_bitcode(&[0, 1]).unwrap(),
+            StoreFormat::Bitcode
+        );
+        let err = StoreFormat::assume_bitcode(&[0x1f, 0x8b, 0]).unwrap_err();
+        assert!(err.to_string().contains("Unrecognized store format"));
+    }
+
+    #[test]
+    fn test_detect_compact() {
+        assert_eq!(
+            StoreFormat::detect(b"RIACMP\x01\x00"),
+            Some(StoreFormat::Compact)
+        );
+    }
+
+    #[test]
+    fn test_detect_indexed() {
+        assert_eq!(
+            StoreFormat::detect(b"RIAIDX\x01\x00"),
+            Some(StoreFormat::Indexed)
+        );
+    }
+
+    #[test]
+    fn test_from_extension() {
+        let format = |path: &str| StoreFormat::from_extension(Path::new(path));
+        assert_eq!(format("results.json"), Some(StoreFormat::Json));
+        assert_eq!(format("results.BITCODE"), Some(StoreFormat::Bitcode));
+        assert_eq!(format("results.idx"), Some(StoreFormat::Indexed));
+        assert_eq!(format("results.compact.zst"), Some(StoreFormat::Compact));
+        assert_eq!(format("results.dat"), None);
+        assert_eq!(format("results.gz"), None);
+        assert_eq!(format("results"), None);
+    }
+
+    #[test]
+    fn test_detect_with_extension() {
+        let detect =
+            |data: &[u8], path: &str| StoreFormat::detect_with_extension(data, Path::new(path));
+        // The contents take precedence when they are conclusive
+        assert_eq!(
+            detect(b"{\"inner\":{}}", "results.bitcode"),
+            Some(StoreFormat::Json)
+        );
+        assert_eq!(
+            detect(b"RIACMP\x01\x00", "results.json"),
+            Some(StoreFormat::Compact)
+        );
+        // Otherwise the extension decides
+        assert_eq!(detect(&[0, 1], "results.json"), Some(StoreFormat::Json));
+        assert_eq!(
+            detect(&[0, 1], "results.bitcode"),
+            Some(StoreFormat::Bitcode)
+        );
+        assert_eq!(detect(&[0, 1], "results.dat"), None);
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn test_json_roundtrip() {
+        let mut data = Vec::new();
+        test_store().to_json_writer(&mut data).unwrap();
+        let store = Store::from_json_reader(data.as_slice()).unwrap();
+        assert!(store.get("main:Ag:Johnson").is_some());
+    }
+
+    #[test]
+    #[cfg(feature = "bitcode")]
+    fn test_bitcode_roundtrip() {
+        let data = test_store().to_bitcode().unwrap();
+        let store = Store::from_bitcode_bytes(&data).unwrap();
+        assert!(store.get("main:Ag:Johnson").is_some());
+    }
+
+    #[test]
+    #[cfg(all(feature = "json", feature = "bitcode"))]
+    fn test_from_bytes_detects_format() {
+        let mut json = Vec::new();
+        test_store().to_json_writer(&mut json).unwrap();
+        let bitcode = test_store().to_bitcode().unwrap();
+
+        assert!(Store::from_bytes(&json)
+            .unwrap()
+            .get("main:Ag:Johnson")
+            .is_some());
+        assert!(Store::from_bytes(&bitcode)
+            .unwrap()
+            .get("main:Ag:Johnson")
+            .is_some());
+        assert!(Store::from_bytes(&test_store().to_indexed().unwrap())
+            .unwrap()
+            .get("main:Ag:Johnson")
+            .is_some());
+    }
+
+    #[test]
+    #[cfg(feature = "bitcode")]
+    fn test_from_bytes_rejects_unrecognized_format() {
+        let err = Store::from_bytes(b"not a store").unwrap_err();
+        assert!(err.to_string().contains("Unrecognized store format"));
+    }
+
+    #[test]
+    #[cfg(all(feature = "json", feature = "zstd"))]
+    fn test_from_bytes_decompresses() {
+        use crate::compress;
+
+        let mut json = Vec::new();
+        test_store().to_json_writer(&mut json).unwrap();
+        let compressed = compress(&json, Compression::Zstd, None).unwrap();
+
+        assert!(Store::from_bytes(&compressed)
+            .unwrap()
+            .get("main:Ag:Johnson")
+            .is_some());
+    }
+}
@@ -0,0 +1,279 @@
+//! Lists of store keys used to include or exclude materials.
+//!
+//! A key list has one entry per line. Blank lines and lines starting with `#`
+//! are ignored. An entry is one of:
+//!
+//! - An exact key, e.g. `main:Ag:Johnson`.
+//! - A glob pattern where `*` matches any run of characters and `?` matches a
+//!   single character, e.g. `glass:*:SCHOTT`.
+//! - A regular expression prefixed with `regex:`, e.g. `regex:^main:A[gu]:`.
+//!   Regular expressions require the `regex` feature.
+use std::collections::HashSet;
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+
+use crate::internal::store::Store;
+
+/// A single entry of a key list.
+#[derive(Debug, Clone)]
+enum KeyPattern {
+    Exact(String),
+    Glob(Vec<char>),
+    #[cfg(feature = "regex")]
+    Regex(regex::Regex),
+}
+
+impl KeyPattern {
+    fn parse(entry: &str) -> Result<KeyPattern> {
+        if let Some(pattern) = entry.strip_prefix("regex:") {
+            #[cfg(feature = "regex")]
+            return Ok(KeyPattern::Regex(regex::Regex::new(pattern.trim())?));
+            #[cfg(not(feature = "regex"))]
+            return Err(anyhow!(
+                "The regular expression {} requires the `regex` feature",
+                pattern
+            ));
+        }
+        if entry.contains(['*', '?']) {
+            return Ok(KeyPattern::Glob(entry.chars().collect()));
+        }
+        Ok(KeyPattern::Exact(entry.to_string()))
+    }
+
+    fn matches(&self, key: &str) -> bool {
+        match self {
+            KeyPattern::Exact(exact) => exact == key,
+            KeyPattern::Glob(pattern) => glob_matches(pattern, &key.chars().collect::<Vec<_>>()),
+            #[cfg(feature = "regex")]
+            KeyPattern::Regex(regex) => regex.is_match(key),
+        }
+    }
+}
+
+/// A list of exact keys and key patterns.
+#[derive(Debug, Clone, Default)]
+pub struct KeyList {
+    /// Exact keys, looked up in constant time.
+    exact: HashSet<String>,
+
+    /// The remaining entries and the lines they were parsed from.
+    patterns: Vec<(String, KeyPattern)>,
+}
+
+impl KeyList {
+    /// Parses a key list.
+    ///
+    /// # Arguments
+    /// - `text`: The contents of a key list, one entry per line.
+    ///
+    /// # Errors
+    /// - If a regular expression is invalid or the `regex` feature is not
+    ///   enabled.
+    pub fn parse(text: &str) -> Result<KeyList> {
+        let mut list = KeyList::default();
+        for (i, line) in text.lines().enumerate() {
+            let entry = line.trim();
+            if entry.is_empty() || entry.starts_with('#') {
+                continue;
+            }
+            match KeyPattern::parse(entry).map_err(|e| anyhow!("Line {}: {}", i + 1, e))? {
+                KeyPattern::Exact(key) => {
+                    list.exact.insert(key);
+                }
+                pattern => list.patterns.push((entry.to_string(), pattern)),
+            }
+        }
+        Ok(list)
+    }
+
+    /// Reads a key list from a file.
+    ///
+    /// # Errors
+    /// - If the file cannot be read or is not a valid key list.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<KeyList> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)?;
+        KeyList::parse(&text).map_err(|e| anyhow!("{}: {}", path.display(), e))
+    }
+
+    /// Returns `true` if any entry of the list matches the key.
+    pub fn matches(&self, key: &str) -> bool {
+        self.exact.contains(key) || self.patterns.iter().any(|(_, p)| p.matches(key))
+    }
+
+    /// Returns the entries of the list that match no key of the store, e.g. to
+    /// warn about misspelt keys.
+    pub fn unmatched<'a>(&'a self, store: &Store) -> Vec<&'a str> {
+        let mut unmatched: Vec<&str> = self
+            .exact
+            .iter()
+            .filter(|key| store.get(key).is_none())
+            .map(String::as_str)
+            .chain(
+                self.patterns
+                    .iter()
+                    .filter(|(_, pattern)| !store.keys().any(|key| pattern.matches(key)))
+                    .map(|(entry, _)| entry.as_str()),
+            )
+            .collect();
+        unmatched.sort();
+        unmatched
+    }
+}
+
+impl Store {
+    /// Keeps the items whose keys are matched by the include list, if any,
+    /// and not matched by the exclude list, if any. Exclusion takes precedence
+    /// over inclusion.
+    ///
+    /// # Arguments
+    /// - `include`: The keys to keep. If `None`, all keys are kept unless
+    ///   excluded.
+    /// - `exclude`: The keys to remove.
+    pub fn retain_keys(&mut self, include: Option<&KeyList>, exclude: Option<&KeyList>) {
+        self.retain(|key, _| {
+            include.is_none_or(|include| include.matches(key))
+                && !exclude.is_some_and(|exclude| exclude.matches(key))
+        });
+    }
+}
+
+/// Matches a glob pattern against text, backtracking to the most recent `*`
+/// on a mismatch.
+fn glob_matches(pattern: &[char], text: &[char]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                Some((star_p, star_t)) => {
+                    p = star_p + 1;
+                    t = star_t + 1;
+                    star = Some((star_p, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Material;
+
+    fn test_store() -> Store {
+        let mut store = Store::default();
+        for key in [
+            "glass:BK7:SCHOTT",
+            "glass:SF11:SCHOTT",
+            "glass:BK7:HIKARI",
+            "main:Ag:Johnson",
+            "main:Au:Johnson",
+        ] {
+            store.insert(
+                key.to_string(),
+                Material {
+                    ..Default::default()
+                },
+            );
+        }
+        store
+    }
+
+    fn sorted_keys(store: &Store) -> Vec<&str> {
+        let mut keys: Vec<&str> = store.keys().map(String::as_str).collect();
+        keys.sort();
+        keys
+    }
+
+    #[test]
+    fn test_glob_matches() {
+        let glob = |pattern: &str, text: &str| {
+            glob_matches(
+                &pattern.chars().collect::<Vec<_>>(),
+                &text.chars().collect::<Vec<_>>(),
+            )
+        };
+        assert!(glob("glass:*:SCHOTT", "glass:BK7:SCHOTT"));
+        assert!(!glob("glass:*:SCHOTT", "glass:BK7:HIKARI"));
+        assert!(glob("main:A?:*", "main:Ag:Johnson"));
+        assert!(glob("*", ""));
+        assert!(glob("*a*b", "xaxxb"));
+        assert!(!glob("*a*b", "xaxxbc"));
+    }
+
+    #[test]
+    fn test_parse_skips_comments_and_blank_lines() {
+        let list = KeyList::parse("# Metals\n\n  main:Ag:Johnson  \n#main:Au:Johnson\n").unwrap();
+        assert!(list.matches("main:Ag:Johnson"));
+        assert!(!list.matches("main:Au:Johnson"));
+        assert!(!list.matches("# Metals"));
+    }
+
+    #[test]
+    fn test_include_glob() {
+        let mut store = test_store();
+        let include = KeyList::parse("glass:*:SCHOTT\n").unwrap();
+        store.retain_keys(Some(&include), None);
+        assert_eq!(
+            sorted_keys(&store),
+            ["glass:BK7:SCHOTT", "glass:SF11:SCHOTT"]
+        );
+    }
+
+    #[test]
+    fn test_exclude_takes_precedence() {
+        let mut store = test_store();
+        let include = KeyList::parse("glass:*\nmain:Ag:Johnson\n").unwrap();
+        let exclude = KeyList::parse("*:HIKARI\nmain:Ag:Johnson\n").unwrap();
+        store.retain_keys(Some(&include), Some(&exclude));
+        assert_eq!(
+            sorted_keys(&store),
+            ["glass:BK7:SCHOTT", "glass:SF11:SCHOTT"]
+        );
+
+        let mut store = test_store();
+        store.retain_keys(None, Some(&exclude));
+        assert_eq!(
+            sorted_keys(&store),
+            ["glass:BK7:SCHOTT", "glass:SF11:SCHOTT", "main:Au:Johnson"]
+        );
+    }
+
+    #[test]
+    fn test_unmatched_entries() {
+        let store = test_store();
+        let list =
+            KeyList::parse("main:Ag:Johnson\nmain:Ag:Jonson\nglass:*:OHARA\nglass:*\n").unwrap();
+        assert_eq!(list.unmatched(&store), ["glass:*:OHARA", "main:Ag:Jonson"]);
+    }
+
+    #[test]
+    #[cfg(feature = "regex")]
+    fn test_regex_entries() {
+        let mut store = test_store();
+        let include = KeyList::parse("regex:^main:A[gu]:").unwrap();
+        store.retain_keys(Some(&include), None);
+        assert_eq!(sorted_keys(&store), ["main:Ag:Johnson", "main:Au:Johnson"]);
+
+        let error = KeyList::parse("\nregex:(").unwrap_err();
+        assert!(error.to_string().starts_with("Line 2:"));
+    }
+
+    #[test]
+    #[cfg(not(feature = "regex"))]
+    fn test_regex_requires_feature() {
+        assert!(KeyList::parse("regex:^main:").is_err());
+    }
+}
@@ -0,0 +1,368 @@
+//! Loading of stores written by older versions of ria.
+//!
+//! Neither JSON nor bitcode stores carry a version number, so the layout of a
+//! serialized store is detected structurally by trying each known layout in
+//! turn. Legacy layouts are upgraded to the current [`Store`] with default
+//! values for any fields that did not exist yet. In particular, layouts
+//! before [`StoreVersion::V3`] do not record the catalog, so their materials
+//! are assumed to be from the nk catalog.
+use std::collections::HashMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::internal::store::{CatalogKind, DispersionData, Material, Store};
+
+/// The layouts of serialized stores that this library can read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum StoreVersion {
+    /// Stores written by ria 1.x. Materials have no divider labels.
+    V1,
+
+    /// Stores written by ria 2.0.0. Materials have no specs and are not
+    /// tagged with their catalog.
+    V2,
+
+    /// Stores whose materials keep the nd and Vd from their SPECS and are
+    /// tagged with the catalog they were read from.
+    V3,
+}
+
+impl StoreVersion {
+    /// The layout written by this version of the library.
+    pub const CURRENT: StoreVersion = StoreVersion::V3;
+}
+
+impl fmt::Display for StoreVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StoreVersion::V1 => write!(f, "1.x"),
+            StoreVersion::V2 => write!(f, "2.0.0"),
+            StoreVersion::V3 => write!(f, "2.x (with SPECS and catalog tags)"),
+        }
+    }
+}
+
+/// The store layout written by ria 1.x.
+///
+/// Unknown fields are denied so that newer stores are never mistaken for this
+/// layout by self-describing formats.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct StoreV1 {
+    inner: HashMap<String, MaterialV1>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct MaterialV1 {
+    shelf: String,
+    book: String,
+    page: String,
+    comments: String,
+    references: String,
+    data: Vec<DispersionData>,
+}
+
+impl From<MaterialV1> for Material {
+    fn from(material: MaterialV1) -> Self {
+        Material {
+            shelf: material.shelf,
+            book: material.book,
+            page: material.page,
+            comments: material.comments,
+            references: material.references,
+            data: material.data,
+            shelf_divider: None,
+            book_divider: None,
+            specs: None,
+            catalog: CatalogKind::Nk,
+        }
+    }
+}
+
+impl From<StoreV1> for Store {
+    fn from(store: StoreV1) -> Self {
+        Store::new(
+            store
+                .inner
+                .into_iter()
+                .map(|(key, material)| (key, material.into()))
+                .collect(),
+        )
+    }
+}
+
+/// The store layout written by ria 2.0.0.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct StoreV2 {
+    inner: HashMap<String, MaterialV2>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct MaterialV2 {
+    shelf: String,
+    book: String,
+    page: String,
+    comments: String,
+    references: String,
+    data: Vec<DispersionData>,
+    shelf_divider: Option<String>,
+    book_divider: Option<String>,
+}
+
+impl From<MaterialV2> for Material {
+    fn from(material: MaterialV2) -> Self {
+        Material {
+            shelf: material.shelf,
+            book: material.book,
+            page: material.page,
+            comments: material.comments,
+            references: material.references,
+            data: material.data,
+            shelf_divider: material.shelf_divider,
+            book_divider: material.book_divider,
+            specs: None,
+            catalog: CatalogKind::Nk,
+        }
+    }
+}
+
+impl From<StoreV2> for Store {
+    fn from(store: StoreV2) -> Self {
+        Store::new(
+            store
+                .inner
+                .into_iter()
+                .map(|(key, material)| (key, material.into()))
+                .collect(),
+        )
+    }
+}
+
+impl Store {
+    /// Deserializes a JSON store written by any supported version of ria.
+    ///
+    /// # Arguments
+    /// - `data`: The serialized store.
+    ///
+    /// # Returns
+    /// The store, upgraded to the current layout, and the layout that was
+    /// detected in the input.
+    ///
+    /// # Errors
+    /// - If the data do not match any known store layout.
+    #[cfg(feature = "json")]
+    pub fn migrate_from_json(data: &[u8]) -> anyhow::Result<(Store, StoreVersion)> {
+        // Legacy layouts are tried first because the current layout would
+        // accept them with the missing fields set to `None`. They deny unknown
+        // fields, so newer stores are rejected at the first material.
+        if let Ok(store) = serde_json::from_slice::<StoreV1>(data) {
+            // An empty store matches every layout, so it is taken to be
+            // current rather than asking for a migration
+            if store.inner.is_empty() {
+                return Ok((Store::default(), StoreVersion::CURRENT));
+            }
+            log::debug!("Upgrading a store from the {} layout", StoreVersion::V1);
+            return Ok((store.into(), StoreVersion::V1));
+        }
+        if let Ok(store) = serde_json::from_slice::<StoreV2>(data) {
+            log::debug!("Upgrading a store from the {} layout", StoreVersion::V2);
+            return Ok((store.into(), StoreVersion::V2));
+        }
+
+        let store: Store = serde_json::from_slice(data)?;
+        Ok((store, StoreVersion::CURRENT))
+    }
+
+    /// Deserializes a bitcode store written by any supported version of ria.
+    ///
+    /// # Arguments
+    /// - `data`: The serialized store.
+    ///
+    /// # Returns
+    /// The store, upgraded to the current layout, and the layout that was
+    /// detected in the input.
+    ///
+    /// # Errors
+    /// - If the data do not match any known store layout.
+    #[cfg(feature = "bitcode")]
+    pub fn migrate_from_bitcode(data: &[u8]) -> anyhow::Result<(Store, StoreVersion)> {
+        // bitcode is not self-describing, but it rejects input that does not
+        // exactly match the target layout, so the newest layout is tried first.
+        let err = match bitcode::deserialize::<Store>(data) {
+            Ok(store) => return Ok((store, StoreVersion::CURRENT)),
+            Err(err) => err,
+        };
+
+        if let Ok(store) = bitcode::deserialize::<StoreV2>(data) {
+            log::debug!("Upgrading a store from the {} layout", StoreVersion::V2);
+            return Ok((store.into(), StoreVersion::V2));
+        }
+        if let Ok(store) = bitcode::deserialize::<StoreV1>(data) {
+            log::debug!("Upgrading a store from the {} layout", StoreVersion::V1);
+            return Ok((store.into(), StoreVersion::V1));
+        }
+
+        Err(anyhow::anyhow!(
+            "The data do not match any known store layout: {}",
+            err
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn store_v1() -> StoreV1 {
+        let mut inner = HashMap::new();
+        inner.insert(
+            "main:Ag:Johnson".to_string(),
+            MaterialV1 {
+                shelf: "main".to_string(),
+                book: "Ag (Silver)".to_string(),
+                page: "Johnson and Christy 1972".to_string(),
+                comments: "".to_string(),
+                references: "".to_string(),
+                data: vec![DispersionData::Formula2 {
+                    wavelength_range: [0.3, 2.5],
+                    c: vec![0.0, 1.03961212, 0.00600069867],
+                }],
+            },
+        );
+        StoreV1 { inner }
+    }
+
+    fn store_v2() -> StoreV2 {
+        let mut inner = HashMap::new();
+        inner.insert(
+            "main:Ag:Johnson".to_string(),
+            MaterialV2 {
+                shelf: "main".to_string(),
+                book: "Ag (Silver)".to_string(),
+                page: "Johnson and Christy 1972".to_string(),
+                comments: "".to_string(),
+                references: "".to_string(),
+                data: vec![],
+                shelf_divider: Some("Ag - Silver".to_string()),
+                book_divider: None,
+            },
+        );
+        StoreV2 { inner }
+    }
+
+    fn store_v3() -> Store {
+        let mut store = Store::default();
+        store.insert(
+            "n2:glass:BK7:Milam".to_string(),
+            Material {
+                shelf: "glass".to_string(),
+                book: "N-BK7".to_string(),
+                page: "Milam 1998".to_string(),
+                catalog: CatalogKind::N2,
+                ..Default::default()
+            },
+        );
+        store
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn test_migrate_from_json_v1() {
+        let data = serde_json::to_vec(&store_v1()).unwrap();
+        let (store, version) = Store::migrate_from_json(&data).unwrap();
+        assert_eq!(version, StoreVersion::V1);
+        let material = store.get("main:Ag:Johnson").unwrap();
+        assert_eq!(material.book, "Ag (Silver)");
+        assert_eq!(material.data.len(), 1);
+        assert!(material.shelf_divider.is_none());
+        assert!(material.book_divider.is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn test_migrate_from_json_v2() {
+        let data = serde_json::to_vec(&store_v2()).unwrap();
+        let (store, version) = Store::migrate_from_json(&data).unwrap();
+        assert_eq!(version, StoreVersion::V2);
+        let material = store.get("main:Ag:Johnson").unwrap();
+        assert_eq!(material.shelf_divider, Some("Ag - Silver".to_string()));
+        assert!(material.specs.is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn test_migrate_from_json_current() {
+        let data = serde_json::to_vec(&store_v3()).unwrap();
+        let (store, version) = Store::migrate_from_json(&data).unwrap();
+        assert_eq!(version, StoreVersion::CURRENT);
+        let material = store.get("n2:glass:BK7:Milam").unwrap();
+        assert_eq!(material.catalog, CatalogKind::N2);
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn test_migrate_from_json_empty() {
+        let data = serde_json::to_vec(&Store::default()).unwrap();
+        let (store, version) = Store::migrate_from_json(&data).unwrap();
+        assert_eq!(version, StoreVersion::CURRENT);
+        assert_eq!(store.keys().count(), 0);
+    }
+
+    #[test]
+    #[cfg(feature = "bitcode")]
+    fn test_migrate_from_bitcode_v1() {
+        let data = bitcode::serialize(&store_v1()).unwrap();
+        let (store, version) = Store::migrate_from_bitcode(&data).unwrap();
+        assert_eq!(version, StoreVersion::V1);
+        assert!(store.get("main:Ag:Johnson").is_some());
+    }
+
+    #[test]
+    #[cfg(feature = "bitcode")]
+    fn test_migrate_from_bitcode_v2() {
+        let data = bitcode::serialize(&store_v2()).unwrap();
+        let (store, version) = Store::migrate_from_bitcode(&data).unwrap();
+        assert_eq!(version, StoreVersion::V2);
+        let material = store.get("main:Ag:Johnson").unwrap();
+        assert_eq!(material.shelf_divider, Some("Ag - Silver".to_string()));
+    }
+
+    #[test]
+    #[cfg(feature = "bitcode")]
+    fn test_migrate_from_bitcode_current() {
+        let data = bitcode::serialize(&store_v3()).unwrap();
+        let (store, version) = Store::migrate_from_bitcode(&data).unwrap();
+        assert_eq!(version, StoreVersion::CURRENT);
+        let material = store.get("n2:glass:BK7:Milam").unwrap();
+        assert_eq!(material.catalog, CatalogKind::N2);
+    }
+
+    #[test]
+    #[cfg(feature = "bitcode")]
+    fn test_migrate_from_bitcode_invalid() {
+        assert!(Store::migrate_from_bitcode(&[1, 2, 3]).is_err());
+    }
+
+    /// A bitcode store written by ria 2.0.0, holding N-BK7 as formula 2 with
+    /// tabulated k.
+    #[test]
+    #[cfg(feature = "bitcode")]
+    fn test_migrate_from_bitcode_checked_in_v2() {
+        let data = include_bytes!("../../tests/data/store_v2.bitcode");
+        let (store, version) = Store::migrate_from_bitcode(data).unwrap();
+        assert_eq!(version, StoreVersion::V2);
+        let material = store.get("glass:BK7:SCHOTT").unwrap();
+        assert!(matches!(
+            material.data.as_slice(),
+            [
+                DispersionData::Formula2 { .. },
+                DispersionData::TabulatedK { .. }
+            ]
+        ));
+        approx::assert_abs_diff_eq!(material.n(0.5876).unwrap(), 1.5168, epsilon = 1e-4);
+    }
+}
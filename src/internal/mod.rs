@@ -3,3 +3,57 @@
 /// Note that this does not exactly match the schema used by
 /// refractiveindex.info. Instead, it uses a flat, key/value store.
 pub(crate) mod store;
+
+/// Reading and writing stores in the supported serialization formats.
+pub(crate) mod io;
+
+/// A compact store format with interned strings and columnar tables.
+#[cfg(feature = "bitcode")]
+pub(crate) mod compact;
+
+/// Optional compression of serialized stores.
+pub(crate) mod compression;
+
+/// Upgrades stores written by older versions of ria to the current layout.
+#[cfg(any(feature = "json", feature = "bitcode"))]
+pub(crate) mod migrate;
+
+/// A store format whose materials are decoded on demand.
+#[cfg(feature = "bitcode")]
+pub(crate) mod indexed;
+
+/// Fuzzy search for materials by key, name and divider.
+pub(crate) mod search;
+
+/// Derived optical properties of materials, such as the Abbe number.
+pub(crate) mod optics;
+
+/// Finding materials by their optical properties.
+pub(crate) mod find;
+
+/// Finding substitutes for a glass.
+pub(crate) mod substitute;
+
+/// A minimal SVG writer for charts.
+pub(crate) mod svg;
+
+/// Glass maps (Abbe diagrams) of nd against Vd.
+pub(crate) mod glass_map;
+
+/// Plots of the refractive index of materials against wavelength.
+pub(crate) mod plot;
+
+/// Lists of store keys used to include or exclude materials.
+pub(crate) mod keys;
+
+/// Composable predicates for selecting materials from a store.
+pub(crate) mod filter;
+
+/// Semantic checks of the dispersion data of materials.
+pub(crate) mod validate;
+
+/// Stores that combine the nk and n2 catalogs.
+pub(crate) mod combine;
+
+/// The nonlinear refractive index n2 of materials.
+pub(crate) mod nonlinear;
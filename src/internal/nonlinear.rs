@@ -0,0 +1,145 @@
+//! The nonlinear refractive index n2 of materials.
+use anyhow::{anyhow, Result};
+
+use crate::internal::store::{
+    extrapolate, interpolate_column, DispersionData, EvalOptions, Material,
+};
+
+impl Material {
+    /// Computes the nonlinear refractive index of the material at the given
+    /// wavelength.
+    ///
+    /// The tabulated n2 data are interpolated linearly, and wavelengths
+    /// outside of the range of the data are an error. See [`Self::n2_with`]
+    /// for other behaviour.
+    ///
+    /// # Arguments
+    /// - `wavelength`: The wavelength in µm at which to evaluate n2.
+    ///
+    /// # Returns
+    /// The nonlinear refractive index in m²/W.
+    ///
+    /// # Errors
+    /// - If the material has no n2 data.
+    /// - If the wavelength is outside the range of the n2 data.
+    pub fn n2(&self, wavelength: f64) -> Result<f64> {
+        self.n2_with(wavelength, &EvalOptions::default())
+    }
+
+    /// Computes the nonlinear refractive index of the material at the given
+    /// wavelength with the given interpolation and extrapolation behaviour.
+    ///
+    /// # Arguments
+    /// - `wavelength`: The wavelength in µm at which to evaluate n2.
+    /// - `options`: How to interpolate the data and how to handle wavelengths
+    ///   outside of their range.
+    ///
+    /// # Returns
+    /// The nonlinear refractive index in m²/W.
+    ///
+    /// # Errors
+    /// - If the material has no n2 data, or they are an empty table.
+    /// - If the wavelength is outside the range of the n2 data and
+    ///   extrapolation is not enabled.
+    pub fn n2_with(&self, wavelength: f64, options: &EvalOptions) -> Result<f64> {
+        let data = self
+            .n2_data()
+            .ok_or(anyhow!("No n2 data found for item."))?;
+        let range = self
+            .n2_range()
+            .ok_or(anyhow!("The tabulated n2 data are empty."))?;
+        let wavelength = extrapolate(wavelength, range, options.extrapolation)?;
+        Ok(interpolate_column(
+            data,
+            1,
+            wavelength,
+            options.interpolation,
+        ))
+    }
+
+    /// Returns the range of wavelengths over which n2 is defined.
+    ///
+    /// # Returns
+    /// The minimum and maximum wavelength, or `None` if the material has no
+    /// n2 data.
+    pub fn n2_range(&self) -> Option<[f64; 2]> {
+        self.data
+            .iter()
+            .find(|data| matches!(data, DispersionData::TabulatedN2 { .. }))?
+            .wavelength_range()
+    }
+
+    /// Returns the rows of the first n2 table of the material.
+    fn n2_data(&self) -> Option<&[[f64; 2]]> {
+        self.data.iter().find_map(|data| match data {
+            DispersionData::TabulatedN2 { data, .. } => Some(data.as_slice()),
+            _ => None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{CatalogKind, Extrapolation};
+    use approx::assert_abs_diff_eq;
+
+    /// n2 of fused silica from a sample n2 page.
+    fn silica() -> Material {
+        Material {
+            shelf: "MAIN - simple inorganic materials".to_string(),
+            book: "SiO2 (Silicon dioxide, Silica, Quartz)".to_string(),
+            page: "Milam 1998: Fused silica".to_string(),
+            references: "D. Milam, Appl. Opt. 37, 546-550 (1998)".to_string(),
+            data: vec![DispersionData::TabulatedN2 {
+                data: vec![[0.355, 3.6e-20], [0.532, 2.4e-20], [1.064, 2.14e-20]],
+                method: Some("Z-scan".to_string()),
+                polarization: Some("linear".to_string()),
+            }],
+            catalog: CatalogKind::N2,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_n2_interpolates() {
+        let material = silica();
+        assert_abs_diff_eq!(material.n2(0.532).unwrap(), 2.4e-20, epsilon = 1e-30);
+        assert_abs_diff_eq!(material.n2(0.798).unwrap(), 2.27e-20, epsilon = 1e-30);
+        assert_eq!(material.n2_range(), Some([0.355, 1.064]));
+    }
+
+    #[test]
+    fn test_n2_out_of_range() {
+        let material = silica();
+        assert!(material.n2(1.55).is_err());
+        let options = EvalOptions {
+            extrapolation: Extrapolation::Clamp,
+            ..Default::default()
+        };
+        assert_abs_diff_eq!(
+            material.n2_with(1.55, &options).unwrap(),
+            2.14e-20,
+            epsilon = 1e-30
+        );
+    }
+
+    #[test]
+    fn test_n2_is_not_the_linear_index() {
+        let material = silica();
+        assert!(material.n2(0.532).is_ok());
+        assert!(material.n(0.532).is_err());
+        assert!(material.data[0].interpolate(0.532).is_err());
+        assert_eq!(material.n_range(), None);
+    }
+
+    #[test]
+    fn test_n2_without_data() {
+        let mut material = silica();
+        material.data = vec![DispersionData::TabulatedN {
+            data: vec![[0.5, 1.46], [0.6, 1.458]],
+        }];
+        assert!(material.n2(0.55).is_err());
+        assert_eq!(material.n2_range(), None);
+    }
+}
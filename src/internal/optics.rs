@@ -0,0 +1,138 @@
+//! Derived optical properties of materials, such as the Abbe number.
+use anyhow::{anyhow, Result};
+
+use crate::internal::store::{EvalOptions, Extrapolation, Material};
+
+/// The wavelength of the helium d line in micrometers.
+pub const D_LINE: f64 = 0.5875618;
+
+/// The wavelength of the hydrogen F line in micrometers.
+pub const F_LINE: f64 = 0.4861327;
+
+/// The wavelength of the hydrogen C line in micrometers.
+pub const C_LINE: f64 = 0.6562725;
+
+/// The wavelength of the mercury g line in micrometers.
+pub const G_LINE: f64 = 0.4358343;
+
+/// The step in micrometers used to differentiate the refractive index.
+const DERIVATIVE_STEP: f64 = 1e-4;
+
+impl Material {
+    /// Computes the refractive index at the helium d line.
+    ///
+    /// # Errors
+    /// - If the material has no real data at the d line.
+    pub fn nd(&self) -> Result<f64> {
+        self.n(D_LINE)
+    }
+
+    /// Computes the Abbe number Vd = (nd - 1) / (nF - nC).
+    ///
+    /// # Errors
+    /// - If the material has no real data at any of the d, F and C lines.
+    /// - If the material has no dispersion between the F and C lines.
+    pub fn abbe_number(&self) -> Result<f64> {
+        let (nd, nf, nc) = (self.n(D_LINE)?, self.n(F_LINE)?, self.n(C_LINE)?);
+        if nf == nc {
+            return Err(anyhow!("The material has no dispersion."));
+        }
+        Ok((nd - 1.0) / (nf - nc))
+    }
+
+    /// Computes the relative partial dispersion PgF = (ng - nF) / (nF - nC).
+    ///
+    /// # Errors
+    /// - If the material has no real data at any of the g, F and C lines.
+    /// - If the material has no dispersion between the F and C lines.
+    pub fn partial_dispersion_gf(&self) -> Result<f64> {
+        let (ng, nf, nc) = (self.n(G_LINE)?, self.n(F_LINE)?, self.n(C_LINE)?);
+        if nf == nc {
+            return Err(anyhow!("The material has no dispersion."));
+        }
+        Ok((ng - nf) / (nf - nc))
+    }
+
+    /// Computes the derivative of the real part of the refractive index with
+    /// respect to wavelength in inverse micrometers.
+    ///
+    /// # Arguments
+    /// - `wavelength`: The wavelength at which to evaluate the derivative.
+    ///
+    /// # Errors
+    /// - If the material has no real data at the wavelength.
+    pub fn dn_dlambda(&self, wavelength: f64) -> Result<f64> {
+        self.n(wavelength)?;
+
+        // Extending the data lets the derivative be taken at the ends of the
+        // data's range
+        let options = EvalOptions {
+            extrapolation: Extrapolation::Extend,
+            ..Default::default()
+        };
+        let above = self.n_with(wavelength + DERIVATIVE_STEP, &options)?;
+        let below = self.n_with(wavelength - DERIVATIVE_STEP, &options)?;
+        Ok((above - below) / (2.0 * DERIVATIVE_STEP))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use approx::assert_abs_diff_eq;
+
+    use super::*;
+    use crate::DispersionData;
+
+    fn n_bk7() -> Material {
+        Material {
+            shelf: "glass".to_string(),
+            book: "N-BK7".to_string(),
+            page: "SCHOTT".to_string(),
+            data: vec![DispersionData::Formula2 {
+                wavelength_range: [0.3, 2.5],
+                c: vec![
+                    0.0,
+                    1.03961212,
+                    0.00600069867,
+                    0.231792344,
+                    0.0200179144,
+                    1.01046945,
+                    103.560653,
+                ],
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_nd_and_abbe_number() {
+        let material = n_bk7();
+        assert_abs_diff_eq!(material.nd().unwrap(), 1.5168, epsilon = 1e-4);
+        assert_abs_diff_eq!(material.abbe_number().unwrap(), 64.17, epsilon = 0.02);
+        assert_abs_diff_eq!(
+            material.partial_dispersion_gf().unwrap(),
+            0.5349,
+            epsilon = 0.001
+        );
+    }
+
+    #[test]
+    fn test_dn_dlambda() {
+        let material = n_bk7();
+        let dn = material.dn_dlambda(D_LINE).unwrap();
+        assert!(dn < -0.03 && dn > -0.05, "dn/dλ = {}", dn);
+        assert!(material.dn_dlambda(0.3).is_ok());
+        assert!(material.dn_dlambda(3.0).is_err());
+    }
+
+    #[test]
+    fn test_abbe_number_without_real_data() {
+        let material = Material {
+            data: vec![DispersionData::TabulatedK {
+                data: vec![[0.4, 0.1], [0.7, 0.2]],
+            }],
+            ..n_bk7()
+        };
+        assert!(material.abbe_number().is_err());
+    }
+}
@@ -0,0 +1,297 @@
+//! Plots of the refractive index of materials against wavelength.
+use anyhow::{anyhow, Result};
+
+use crate::internal::store::{DispersionData, Material, Store};
+use crate::internal::svg::{
+    padded_log_range, padded_range, Scale, Svg, HEIGHT, MARGIN_BOTTOM, MARGIN_LEFT, MARGIN_RIGHT,
+    MARGIN_TOP, PALETTE, WIDTH,
+};
+
+/// The number of wavelengths at which formulas are evaluated.
+const FORMULA_SAMPLES: usize = 200;
+
+/// The dash pattern of k curves.
+const K_DASH: &str = "6 4";
+
+/// Options that control how materials are plotted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PlotOptions {
+    /// Draws k on a logarithmic axis. Values of k that are not positive are
+    /// left out.
+    pub log_k: bool,
+}
+
+/// A curve of n or k against wavelength.
+struct Curve {
+    points: Vec<[f64; 2]>,
+
+    /// `true` if the points are tabulated data rather than evaluations of a
+    /// formula.
+    tabulated: bool,
+}
+
+impl Store {
+    /// Plots n, and k if available, against wavelength for one or more
+    /// materials as a self-contained SVG.
+    ///
+    /// n is drawn with solid lines against the left axis and k with dashed
+    /// lines against the right axis. Each curve covers its data's range.
+    /// Tabulated data are marked at their sample points, while formulas are
+    /// drawn as smooth curves.
+    ///
+    /// # Arguments
+    /// - `keys`: The keys of the materials to plot.
+    /// - `options`: How to draw the plot.
+    ///
+    /// # Errors
+    /// - If a key is not in the store.
+    /// - If none of the materials have any data to plot.
+    pub fn plot(&self, keys: &[&str], options: &PlotOptions) -> Result<String> {
+        let mut series = Vec::new();
+        for key in keys {
+            let material = self
+                .get(key)
+                .ok_or(anyhow!("Key not found in store: {}", key))?;
+            let (n, mut k) = curves(material);
+            if let Some(k) = k.as_mut().filter(|_| options.log_k) {
+                k.points.retain(|[_, k]| *k > 0.0);
+            }
+            series.push((*key, n, k.filter(|k| !k.points.is_empty())));
+        }
+
+        let n_points = || series.iter().filter_map(|(_, n, _)| n.as_ref());
+        let k_points = || series.iter().filter_map(|(_, _, k)| k.as_ref());
+        let all = || n_points().chain(k_points()).flat_map(|c| &c.points);
+        if all().next().is_none() {
+            return Err(anyhow!("None of the materials have data to plot."));
+        }
+
+        let (left, right) = (MARGIN_LEFT, WIDTH - MARGIN_RIGHT);
+        let (top, bottom) = (MARGIN_TOP, HEIGHT - MARGIN_BOTTOM);
+        let (x_min, x_max) = padded_range(all().map(|[wavelength, _]| *wavelength));
+        let x = Scale::new(x_min, x_max, left, right);
+        let (n_min, n_max) = padded_range(n_points().flat_map(|c| &c.points).map(|[_, n]| *n));
+        let y_n = Scale::new(n_min, n_max, bottom, top);
+        let k_values = || k_points().flat_map(|c| &c.points).map(|[_, k]| *k);
+        let y_k = if options.log_k {
+            let (k_min, k_max) = padded_log_range(k_values());
+            Scale::log(k_min, k_max, bottom, top)
+        } else {
+            let (k_min, k_max) = padded_range(k_values());
+            Scale::new(k_min.min(0.0), k_max, bottom, top)
+        };
+
+        let mut svg = Svg::new();
+        let has_n = n_points().next().is_some();
+        let has_k = k_points().next().is_some();
+        svg.axes(&x, &y_n, "Wavelength (µm)", if has_n { "n" } else { "" });
+        if has_k {
+            svg.right_axis(&y_k, if options.log_k { "k (log)" } else { "k" }, "black");
+        }
+
+        for (i, (key, n, k)) in series.iter().enumerate() {
+            let color = PALETTE[i % PALETTE.len()];
+            for (curve, y, dash) in [(n, &y_n, None), (k, &y_k, Some(K_DASH))] {
+                let Some(curve) = curve else { continue };
+                let pixels: Vec<(f64, f64)> = curve
+                    .points
+                    .iter()
+                    .map(|[wavelength, value]| (x.map(*wavelength), y.map(*value)))
+                    .collect();
+                svg.polyline(&pixels, color, dash);
+                if curve.tabulated {
+                    for (px, py) in pixels {
+                        svg.circle(px, py, 2.5, color, None);
+                    }
+                }
+            }
+
+            // Legend
+            let ly = top + 18.0 * (i as f64 + 1.0);
+            svg.line(left + 10.0, ly - 4.0, left + 30.0, ly - 4.0, color);
+            svg.text(left + 36.0, ly, "start", key, Some(color));
+        }
+
+        let ly = top + 18.0 * (series.len() as f64 + 1.0);
+        svg.text(
+            left + 10.0,
+            ly,
+            "start",
+            "solid: n, dashed: k, dots: tabulated points",
+            None,
+        );
+
+        Ok(svg.finish())
+    }
+}
+
+/// Returns the n and k curves of a material.
+fn curves(material: &Material) -> (Option<Curve>, Option<Curve>) {
+    let table = |rows: Vec<[f64; 2]>| {
+        Some(Curve {
+            points: rows,
+            tabulated: true,
+        })
+    };
+
+    let mut n = None;
+    let mut k = None;
+    for data in &material.data {
+        match data {
+            DispersionData::TabulatedK { data } => {
+                k = k.or_else(|| table(data.clone()));
+            }
+            DispersionData::TabulatedN { data } => {
+                n = n.or_else(|| table(data.clone()));
+            }
+            DispersionData::TabulatedNK { data } => {
+                n = n.or_else(|| table(data.iter().map(|r| [r[0], r[1]]).collect()));
+                k = k.or_else(|| table(data.iter().map(|r| [r[0], r[2]]).collect()));
+            }
+            formula @ (DispersionData::Formula1 { .. }
+            | DispersionData::Formula2 { .. }
+            | DispersionData::Formula3 { .. }
+            | DispersionData::Formula4 { .. }
+            | DispersionData::Formula5 { .. }
+            | DispersionData::Formula6 { .. }
+            | DispersionData::Formula7 { .. }
+            | DispersionData::Formula8 { .. }
+            | DispersionData::Formula9 { .. }) => {
+                n = n.or_else(|| {
+                    let [min, max] = formula.wavelength_range()?;
+                    let points = (0..FORMULA_SAMPLES)
+                        .map(|i| min + (max - min) * i as f64 / (FORMULA_SAMPLES - 1) as f64)
+                        .filter_map(|wavelength| {
+                            let (n, _) = formula.interpolate(wavelength).ok()?;
+                            Some([wavelength, n])
+                        })
+                        .filter(|[_, n]| n.is_finite())
+                        .collect();
+                    Some(Curve {
+                        points,
+                        tabulated: false,
+                    })
+                });
+            }
+            // Neither gives n or k
+            DispersionData::TabulatedN2 { .. } | DispersionData::Unknown { .. } => {}
+        }
+    }
+    (n, k)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn test_material(data: Vec<DispersionData>) -> Material {
+        Material {
+            shelf: "main".to_string(),
+            data,
+            ..Default::default()
+        }
+    }
+
+    fn test_store() -> Store {
+        let mut store = Store::default();
+        store.insert(
+            "glass:BK7:SCHOTT".to_string(),
+            test_material(vec![DispersionData::Formula2 {
+                wavelength_range: [0.3, 2.5],
+                c: vec![
+                    0.0,
+                    1.03961212,
+                    0.00600069867,
+                    0.231792344,
+                    0.0200179144,
+                    1.01046945,
+                    103.560653,
+                ],
+            }]),
+        );
+        store.insert(
+            "main:Ag:Johnson".to_string(),
+            test_material(vec![DispersionData::TabulatedNK {
+                data: vec![[0.4, 0.05, 2.1], [0.5, 0.05, 3.1], [0.6, 0.06, 4.0]],
+            }]),
+        );
+        store.insert("main:Empty:Page".to_string(), test_material(vec![]));
+        store
+    }
+
+    #[test]
+    fn test_plot_formula_and_table() {
+        let store = test_store();
+        let svg = store
+            .plot(
+                &["glass:BK7:SCHOTT", "main:Ag:Johnson"],
+                &PlotOptions::default(),
+            )
+            .unwrap();
+        assert!(svg.starts_with("<svg"));
+        // n of both materials and k of Ag
+        assert_eq!(svg.matches("<polyline").count(), 3);
+        assert_eq!(
+            svg.matches(&format!("stroke-dasharray=\"{}\"", K_DASH))
+                .count(),
+            1
+        );
+        // Only the tabulated n and k points are marked
+        assert_eq!(svg.matches("<circle").count(), 6);
+        assert!(svg.contains(">main:Ag:Johnson</text>"));
+        assert!(svg.contains("k</text>"));
+    }
+
+    #[test]
+    fn test_plot_log_k() {
+        let store = test_store();
+        let svg = store
+            .plot(&["main:Ag:Johnson"], &PlotOptions { log_k: true })
+            .unwrap();
+        assert!(svg.contains("k (log)"));
+    }
+
+    #[test]
+    fn test_plot_log_k_over_several_decades() {
+        let mut store = Store::default();
+        store.insert(
+            "main:Au:Johnson".to_string(),
+            test_material(vec![DispersionData::TabulatedK {
+                data: vec![[0.4, 0.01], [0.5, 1.0], [0.6, 4.0]],
+            }]),
+        );
+        let svg = store
+            .plot(&["main:Au:Johnson"], &PlotOptions { log_k: true })
+            .unwrap();
+        assert!(!svg.contains("NaN"));
+        assert_eq!(svg.matches("<circle").count(), 3);
+    }
+
+    #[test]
+    fn test_curves_skip_n2_data() {
+        let material = test_material(vec![
+            DispersionData::TabulatedN2 {
+                data: vec![[0.5, 3.0e-20], [1.0, 2.5e-20]],
+                method: None,
+                polarization: None,
+            },
+            DispersionData::TabulatedN {
+                data: vec![[0.5, 1.5], [1.0, 1.4]],
+            },
+        ]);
+        let (n, k) = curves(&material);
+        assert_eq!(n.unwrap().points, [[0.5, 1.5], [1.0, 1.4]]);
+        assert!(k.is_none());
+    }
+
+    #[test]
+    fn test_plot_errors() {
+        let store = test_store();
+        assert!(store
+            .plot(&["main:Missing:Page"], &PlotOptions::default())
+            .is_err());
+        assert!(store
+            .plot(&["main:Empty:Page"], &PlotOptions::default())
+            .is_err());
+    }
+}
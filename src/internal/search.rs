@@ -0,0 +1,310 @@
+//! Fuzzy search for materials by key, name and divider.
+//!
+//! Queries and material fields are normalized the same way before they are
+//! compared: case is folded, subscript and superscript digits become ordinary
+//! digits, the micro sign becomes `u`, and text is split into tokens at any
+//! character that is not alphanumeric and at every boundary between letters
+//! and digits. "N-BK7", "bk7" and "schott bk 7" therefore all contain the
+//! tokens `bk` and `7`.
+use crate::internal::combine::key_parts;
+use crate::internal::store::{Material, Store};
+
+/// The weight of a token that matches a divider or shelf name rather than the
+/// key or the book and page names.
+const SECONDARY_WEIGHT: f64 = 0.75;
+
+impl Store {
+    /// Searches the store for materials matching a free-form query.
+    ///
+    /// Every token of the query must match a token of the material's key,
+    /// book or page name, shelf name, or dividers, either exactly, as a prefix,
+    /// or, for longer tokens, with a single typo.
+    ///
+    /// # Arguments
+    /// - `query`: The text to search for, e.g. "schott bk 7" or "silver
+    ///   johnson".
+    ///
+    /// # Returns
+    /// The score between 0 and 1 and the key of each matching material, best
+    /// matches first. Materials with equal scores are ordered by key.
+    pub fn search(&self, query: &str) -> Vec<(f64, &str)> {
+        let query_tokens = tokenize(query);
+        if query_tokens.is_empty() {
+            return Vec::new();
+        }
+        let query_compact = query_tokens.concat();
+
+        let mut results: Vec<(f64, &str)> = self
+            .iter()
+            .filter_map(|(key, material)| {
+                let score = score(key, material, &query_tokens, &query_compact)?;
+                Some((score, key.as_str()))
+            })
+            .collect();
+        results.sort_by(|a, b| b.0.total_cmp(&a.0).then_with(|| a.1.cmp(b.1)));
+        results
+    }
+}
+
+/// Scores a material against the tokens of a query.
+///
+/// # Returns
+/// The score, or `None` if any token of the query does not match.
+fn score(
+    key: &str,
+    material: &Material,
+    query_tokens: &[String],
+    query_compact: &str,
+) -> Option<f64> {
+    let primary: Vec<String> = [key, &material.book, &material.page]
+        .iter()
+        .flat_map(|field| tokenize(field))
+        .collect();
+    let secondary: Vec<String> = [
+        Some(&material.shelf),
+        material.shelf_divider.as_ref(),
+        material.book_divider.as_ref(),
+    ]
+    .into_iter()
+    .flatten()
+    .flat_map(|field| tokenize(field))
+    .collect();
+
+    let mut total = 0.0;
+    for query_token in query_tokens {
+        let best = best_match(query_token, &primary)
+            .max(best_match(query_token, &secondary) * SECONDARY_WEIGHT);
+        if best == 0.0 {
+            return None;
+        }
+        total += best;
+    }
+    let mean = total / query_tokens.len() as f64;
+
+    // A query that spells out the whole book, ignoring separators, is the
+    // strongest signal, e.g. "n-bk7" for the book named N-BK7
+    let book_key = key_parts(key).nth(1).unwrap_or_default();
+    let spells_book = [book_key, material.book.as_str()]
+        .iter()
+        .any(|book| tokenize(book).concat() == query_compact);
+
+    Some(if spells_book {
+        0.8 * mean + 0.2
+    } else {
+        0.8 * mean
+    })
+}
+
+/// Returns the score of the best match of a query token among the tokens of
+/// a material, or 0 if there is none.
+fn best_match(query_token: &str, tokens: &[String]) -> f64 {
+    tokens
+        .iter()
+        .map(|token| token_score(query_token, token))
+        .fold(0.0, f64::max)
+}
+
+/// Scores how well a query token matches a single token.
+fn token_score(query_token: &str, token: &str) -> f64 {
+    if query_token == token {
+        return 1.0;
+    }
+
+    // Single characters and numbers only match exactly; "7" should not match
+    // "70"
+    let is_number = query_token.chars().all(|c| c.is_ascii_digit());
+    if query_token.chars().count() < 2 || is_number {
+        return 0.0;
+    }
+
+    if token.starts_with(query_token) {
+        return 0.8;
+    }
+    if query_token.chars().count() >= 4 && within_one_edit(query_token, token) {
+        return 0.6;
+    }
+    0.0
+}
+
+/// Returns `true` if the strings differ by at most one insertion, deletion or
+/// substitution.
+fn within_one_edit(a: &str, b: &str) -> bool {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let (short, long) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    if long.len() - short.len() > 1 {
+        return false;
+    }
+
+    let prefix = short.iter().zip(&long).take_while(|(x, y)| x == y).count();
+    if prefix == short.len() {
+        // Equal, or the longer string has one extra character at the end
+        return true;
+    }
+    if short.len() == long.len() {
+        short[prefix + 1..] == long[prefix + 1..]
+    } else {
+        short[prefix..] == long[prefix + 1..]
+    }
+}
+
+/// Normalizes text and splits it into tokens.
+fn tokenize(text: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut token = String::new();
+    for c in text
+        .chars()
+        .flat_map(char::to_lowercase)
+        .map(normalize_char)
+    {
+        let boundary = token
+            .chars()
+            .last()
+            .is_some_and(|last| last.is_ascii_digit() != c.is_ascii_digit());
+        if (!c.is_alphanumeric() || boundary) && !token.is_empty() {
+            tokens.push(std::mem::take(&mut token));
+        }
+        if c.is_alphanumeric() {
+            token.push(c);
+        }
+    }
+    if !token.is_empty() {
+        tokens.push(token);
+    }
+    tokens
+}
+
+/// Maps subscript and superscript digits to ASCII digits and the micro sign
+/// and Greek mu to `u`.
+fn normalize_char(c: char) -> char {
+    match c {
+        '₀'..='₉' => char::from(b'0' + (c as u32 - '₀' as u32) as u8),
+        '⁰' => '0',
+        '¹' => '1',
+        '²' => '2',
+        '³' => '3',
+        '⁴'..='⁹' => char::from(b'4' + (c as u32 - '⁴' as u32) as u8),
+        'µ' | 'μ' => 'u',
+        c => c,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn test_material(shelf: &str, book: &str, page: &str, divider: Option<&str>) -> Material {
+        Material {
+            shelf: shelf.to_string(),
+            book: book.to_string(),
+            page: page.to_string(),
+            shelf_divider: divider.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    fn test_store() -> Store {
+        let mut store = Store::default();
+        store.insert(
+            "glass:BK7:SCHOTT".to_string(),
+            test_material(
+                "GLASS - optical glasses",
+                "N-BK7",
+                "SCHOTT N-BK7",
+                Some("SCHOTT - optical glass"),
+            ),
+        );
+        store.insert(
+            "glass:BAK1:SCHOTT".to_string(),
+            test_material(
+                "GLASS - optical glasses",
+                "N-BAK1",
+                "SCHOTT N-BAK1",
+                Some("SCHOTT - optical glass"),
+            ),
+        );
+        store.insert(
+            "main:Ag:Johnson".to_string(),
+            test_material(
+                "MAIN - simple inorganic materials",
+                "Ag (Silver)",
+                "Johnson and Christy 1972: n,k 0.188–1.94 µm",
+                Some("Ag - Silver"),
+            ),
+        );
+        store.insert(
+            "main:Al2O3:Malitson".to_string(),
+            test_material(
+                "MAIN - simple inorganic materials",
+                "Al₂O₃ (Aluminium sesquioxide, Sapphire, Alumina)",
+                "Malitson 1962: α-Al₂O₃ (o); n 0.2–5.0 µm",
+                None,
+            ),
+        );
+        store
+    }
+
+    fn keys<'a>(results: &[(f64, &'a str)]) -> Vec<&'a str> {
+        results.iter().map(|(_, key)| *key).collect()
+    }
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(tokenize("N-BK7"), ["n", "bk", "7"]);
+        assert_eq!(tokenize("schott bk 7"), ["schott", "bk", "7"]);
+        assert_eq!(tokenize("Al₂O₃"), ["al", "2", "o", "3"]);
+        assert_eq!(tokenize("0.2–5.0 µm"), ["0", "2", "5", "0", "um"]);
+        assert!(tokenize(" - ").is_empty());
+    }
+
+    #[test]
+    fn test_within_one_edit() {
+        assert!(within_one_edit("johnson", "johnsen"));
+        assert!(within_one_edit("johnson", "jonson"));
+        assert!(within_one_edit("silver", "silvers"));
+        assert!(within_one_edit("silver", "silver"));
+        assert!(!within_one_edit("silver", "sliver2"));
+    }
+
+    #[test]
+    fn test_search_glass() {
+        let store = test_store();
+        for query in ["bk7", "N-BK7", "schott bk 7", "BK-7"] {
+            let results = store.search(query);
+            assert_eq!(keys(&results), ["glass:BK7:SCHOTT"], "query {}", query);
+        }
+    }
+
+    #[test]
+    fn test_search_ranks_results() {
+        let store = test_store();
+        let results = store.search("schott");
+        assert_eq!(keys(&results), ["glass:BAK1:SCHOTT", "glass:BK7:SCHOTT"]);
+        assert!(results
+            .iter()
+            .all(|(score, _)| *score > 0.0 && *score <= 1.0));
+
+        let results = store.search("n-bk7");
+        assert!(results[0].0 > store.search("schott")[0].0);
+    }
+
+    #[test]
+    fn test_search_names_and_typos() {
+        let store = test_store();
+        assert_eq!(keys(&store.search("silver johnson")), ["main:Ag:Johnson"]);
+        assert_eq!(keys(&store.search("Silver Johnsen")), ["main:Ag:Johnson"]);
+        assert_eq!(keys(&store.search("al2o3")), ["main:Al2O3:Malitson"]);
+        assert_eq!(keys(&store.search("sapphire")), ["main:Al2O3:Malitson"]);
+    }
+
+    #[test]
+    fn test_search_no_match() {
+        let store = test_store();
+        assert!(store.search("silver schott").is_empty());
+        assert!(store.search("").is_empty());
+        assert!(store
+            .search("7")
+            .iter()
+            .all(|(_, key)| *key == "glass:BK7:SCHOTT"));
+    }
+}
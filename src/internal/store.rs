@@ -4,13 +4,13 @@ use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
 
 /// A flat, key-value store for material refractive index data.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Store {
     inner: HashMap<String, Material>,
 }
 
 /// A single item in the store containing materials data.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Material {
     pub shelf: String,
     pub book: String,
@@ -25,6 +25,40 @@ pub struct Material {
     /// (e.g. "Gas", "Bulk", "Thin film", "Experimental data: bulk, thick
     /// film"). `None` if no DIVIDER precedes the page.
     pub book_divider: Option<String>,
+    /// The catalog values in the page's SPECS, such as the nd and Vd of a
+    /// glass. `None` if the page has no SPECS.
+    pub specs: Option<Specs>,
+    /// The database catalog that the page was read from.
+    pub catalog: CatalogKind,
+}
+
+/// The catalogs of the refractiveindex.info database.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum CatalogKind {
+    /// The linear refractive index n and extinction coefficient k.
+    #[default]
+    Nk,
+    /// The nonlinear refractive index n2.
+    N2,
+}
+
+impl std::fmt::Display for CatalogKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CatalogKind::Nk => write!(f, "nk"),
+            CatalogKind::N2 => write!(f, "n2"),
+        }
+    }
+}
+
+/// Values published with a material in the SPECS of its page.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub struct Specs {
+    /// The refractive index at the d line.
+    pub nd: Option<f64>,
+    /// The Abbe number at the d line.
+    pub vd: Option<f64>,
 }
 
 #[derive(Debug)]
@@ -32,10 +66,15 @@ enum DataType {
     Real,
     Imaginary,
     Both,
+    Nonlinear,
+    Unsupported,
 }
 
 /// The refractive index data associated with a material.
-#[derive(Serialize, Deserialize, Debug)]
+///
+/// Stores identify variants by their index, so new variants must only be
+/// appended after the existing ones.
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum DispersionData {
     TabulatedK {
         data: Vec<[f64; 2]>,
@@ -100,6 +139,60 @@ pub enum DispersionData {
         wavelength_range: [f64; 2],
         c: Vec<f64>,
     },
+
+    /// The nonlinear refractive index n2 against wavelength.
+    TabulatedN2 {
+        /// Rows of the wavelength in µm and n2 in m²/W.
+        data: Vec<[f64; 2]>,
+        /// How n2 was measured, e.g. "Z-scan", if the page says.
+        method: Option<String>,
+        /// The polarization of the light, if the page says.
+        polarization: Option<String>,
+    },
+
+    /// Data of a type that this library does not support, such as a type
+    /// added to the database after this version was released. They are kept
+    /// so that no data are lost, but cannot be evaluated.
+    Unknown {
+        /// The type of the data as given in the database.
+        type_name: String,
+        /// The other fields of the data as YAML.
+        raw: String,
+    },
+}
+
+/// How tabulated data are interpolated between their sample points.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Interpolation {
+    /// Straight lines between adjacent points.
+    #[default]
+    Linear,
+
+    /// A monotone piecewise cubic (Fritsch-Butland), which is smooth but does
+    /// not overshoot the data.
+    Cubic,
+}
+
+/// How wavelengths outside of the range of the dispersion data are handled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Extrapolation {
+    /// Return an error.
+    #[default]
+    Error,
+
+    /// Use the value at the nearest end of the range.
+    Clamp,
+
+    /// Evaluate formulas outside of their range, and extend tabulated data
+    /// along the line through their first or last two points.
+    Extend,
+}
+
+/// Options for evaluating dispersion data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct EvalOptions {
+    pub interpolation: Interpolation,
+    pub extrapolation: Extrapolation,
 }
 
 impl Store {
@@ -132,6 +225,11 @@ impl Store {
         self.inner.keys()
     }
 
+    /// Returns an iterator over the keys and items in the store.
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Material)> {
+        self.inner.iter()
+    }
+
     /// Retains only the items in the store that satisfy the given predicate.
     ///
     /// # Arguments
@@ -160,6 +258,16 @@ impl Store {
     }
 }
 
+impl IntoIterator for Store {
+    type Item = (String, Material);
+    type IntoIter = std::collections::hash_map::IntoIter<String, Material>;
+
+    /// Consumes the store, returning its keys and items.
+    fn into_iter(self) -> Self::IntoIter {
+        self.inner.into_iter()
+    }
+}
+
 impl Default for Store {
     fn default() -> Self {
         let database = HashMap::new();
@@ -168,6 +276,12 @@ impl Default for Store {
 }
 
 impl Material {
+    /// Returns a human-readable name for the material made of its book and
+    /// page names, e.g. "Ag (Silver): Johnson and Christy 1972".
+    pub fn name(&self) -> String {
+        format!("{}: {}", self.book, self.page)
+    }
+
     /// Computes the real part of the refractive index of the material at the
     /// given wavelength.
     ///
@@ -183,12 +297,30 @@ impl Material {
     /// - If no real data is found for the item.
     /// - If the wavelength is outside the range of the real data.
     pub fn n(&self, wavelength: f64) -> Result<f64> {
+        self.n_with(wavelength, &EvalOptions::default())
+    }
+
+    /// Computes the real part of the refractive index of the material at the
+    /// given wavelength with the given interpolation and extrapolation
+    /// behaviour.
+    ///
+    /// # Arguments
+    /// - `wavelength`: The wavelength at which to evaluate the refractive
+    ///   index.
+    /// - `options`: How to interpolate tabulated data and how to handle
+    ///   wavelengths outside of the range of the data.
+    ///
+    /// # Errors
+    /// - If no real data is found for the item.
+    /// - If the wavelength is outside the range of the real data and
+    ///   extrapolation is not enabled.
+    pub fn n_with(&self, wavelength: f64, options: &EvalOptions) -> Result<f64> {
         let data = self
             .data
             .iter()
             .find(|d| matches!(d.data_type(), DataType::Real | DataType::Both));
         let (n, _) = match data {
-            Some(data) => data.interpolate(wavelength)?,
+            Some(data) => data.interpolate_with(wavelength, options)?,
             None => return Err(anyhow!("No real data found for item.")),
         };
         Ok(n)
@@ -208,20 +340,54 @@ impl Material {
     /// # Errors
     /// - If the wavelength is outside the range of the imaginary data.
     pub fn k(&self, wavelength: f64) -> Result<Option<f64>> {
+        self.k_with(wavelength, &EvalOptions::default())
+    }
+
+    /// Computes the imaginary part of the refractive index of the material at
+    /// the given wavelength with the given interpolation and extrapolation
+    /// behaviour.
+    ///
+    /// # Arguments
+    /// - `wavelength`: The wavelength at which to evaluate the refractive
+    ///   index.
+    /// - `options`: How to interpolate tabulated data and how to handle
+    ///   wavelengths outside of the range of the data.
+    ///
+    /// # Errors
+    /// - If the wavelength is outside the range of the imaginary data and
+    ///   extrapolation is not enabled.
+    pub fn k_with(&self, wavelength: f64, options: &EvalOptions) -> Result<Option<f64>> {
         let data = self
             .data
             .iter()
             .find(|d| matches!(d.data_type(), DataType::Imaginary | DataType::Both));
         match data {
-            Some(data) => Ok(data.interpolate(wavelength)?.1),
+            Some(data) => Ok(data.interpolate_with(wavelength, options)?.1),
             None => Ok(None),
         }
     }
+
+    /// Returns the range of wavelengths over which the real part of the
+    /// refractive index is defined.
+    ///
+    /// # Returns
+    /// The minimum and maximum wavelength, or `None` if the material has no
+    /// real data.
+    pub fn n_range(&self) -> Option<[f64; 2]> {
+        self.data
+            .iter()
+            .find(|d| matches!(d.data_type(), DataType::Real | DataType::Both))?
+            .wavelength_range()
+    }
 }
 
 impl DispersionData {
     /// Computes the value of the dispersion curve at the given wavelength.
     ///
+    /// Tabulated data are interpolated linearly, and wavelengths outside of
+    /// the range of the data are an error. See [`Self::interpolate_with`] for
+    /// other behaviour.
+    ///
     /// # Arguments
     /// - `wavelength`: The wavelength at which to evaluate the dispersion
     ///   curve.
@@ -234,49 +400,74 @@ impl DispersionData {
     /// # Errors
     /// - If the wavelength is outside the range of dispersion data.
     pub fn interpolate(&self, wavelength: f64) -> Result<(f64, Option<f64>)> {
+        self.interpolate_with(wavelength, &EvalOptions::default())
+    }
+
+    /// Computes the value of the dispersion curve at the given wavelength
+    /// with the given interpolation and extrapolation behaviour.
+    ///
+    /// # Arguments
+    /// - `wavelength`: The wavelength at which to evaluate the dispersion
+    ///   curve.
+    /// - `options`: How to interpolate tabulated data and how to handle
+    ///   wavelengths outside of the range of the data.
+    ///
+    /// # Returns
+    /// The value of the dispersion curve at the given wavelength. The first
+    /// value is the real part of the refractive index, and the second value
+    /// is the imaginary part of the refractive index. TabulatedK data have no
+    /// real part, so the first value is NaN for them.
+    ///
+    /// # Errors
+    /// - If the wavelength is outside the range of dispersion data and
+    ///   `options.extrapolation` is [`Extrapolation::Error`].
+    /// - If the data are an empty table.
+    /// - If the data are n2 data, which do not give the refractive index; see
+    ///   [`Material::n2`].
+    /// - If the data are of an unknown type.
+    pub fn interpolate_with(
+        &self,
+        wavelength: f64,
+        options: &EvalOptions,
+    ) -> Result<(f64, Option<f64>)> {
+        match self {
+            Self::TabulatedN2 { .. } => {
+                return Err(anyhow!(
+                    "n2 data do not give the linear refractive index; use Material::n2"
+                ))
+            }
+            Self::Unknown { type_name, .. } => {
+                return Err(anyhow!(
+                    "Data of the unsupported type \"{}\" cannot be evaluated",
+                    type_name
+                ))
+            }
+            _ => {}
+        }
+        let range = self
+            .wavelength_range()
+            .ok_or(anyhow!("The tabulated dispersion data are empty."))?;
+        let wavelength = extrapolate(wavelength, range, options.extrapolation)?;
+
         let n: f64 = match &self {
-            Self::Formula1 {
-                wavelength_range,
-                c,
-            } => {
+            Self::Formula1 { c, .. } => {
                 // Sellmeier (preferred)
-                if wavelength < wavelength_range[0] || wavelength > wavelength_range[1] {
-                    return Err(anyhow!(
-                        "The requested wavelength is outside the range of the available dispersion data."
-                    ));
-                }
                 let mut sum = 0.0;
                 for i in (1..c.len()).step_by(2) {
                     sum += c[i] * wavelength.powi(2) / (wavelength.powi(2) - c[i + 1].powi(2));
                 }
                 (1.0 + c[0] + sum).sqrt()
             }
-            Self::Formula2 {
-                wavelength_range,
-                c,
-            } => {
+            Self::Formula2 { c, .. } => {
                 // Sellmeier-2
-                if wavelength < wavelength_range[0] || wavelength > wavelength_range[1] {
-                    return Err(anyhow!(
-                        "The requested wavelength is outside the range of the available dispersion data."
-                    ));
-                }
                 let mut sum = 0.0;
                 for i in (1..c.len()).step_by(2) {
                     sum += c[i] * wavelength.powi(2) / (wavelength.powi(2) - c[i + 1]);
                 }
                 (1.0 + c[0] + sum).sqrt()
             }
-            Self::Formula3 {
-                wavelength_range,
-                c,
-            } => {
+            Self::Formula3 { c, .. } => {
                 // Polynomial
-                if wavelength < wavelength_range[0] || wavelength > wavelength_range[1] {
-                    return Err(anyhow!(
-                        "The requested wavelength is outside the range of the available dispersion data."
-                    ));
-                }
 
                 let mut sum = 0.0;
                 for i in (1..c.len()).step_by(2) {
@@ -284,16 +475,8 @@ impl DispersionData {
                 }
                 (c[0] + sum).sqrt()
             }
-            Self::Formula4 {
-                wavelength_range,
-                c,
-            } => {
+            Self::Formula4 { c, .. } => {
                 // RefractiveIndex.INFO
-                if wavelength < wavelength_range[0] || wavelength > wavelength_range[1] {
-                    return Err(anyhow!(
-                        "The requested wavelength is outside the range of the available dispersion data."
-                    ));
-                }
 
                 let mut sum = 0.0;
                 for i in (1..c.len()).step_by(4) {
@@ -307,16 +490,8 @@ impl DispersionData {
                 }
                 (c[0] + sum).sqrt()
             }
-            Self::Formula5 {
-                wavelength_range,
-                c,
-            } => {
+            Self::Formula5 { c, .. } => {
                 // Cauchy
-                if wavelength < wavelength_range[0] || wavelength > wavelength_range[1] {
-                    return Err(anyhow!(
-                        "The requested wavelength is outside the range of the available dispersion data."
-                    ));
-                }
 
                 let mut sum = 0.0;
                 for i in (1..c.len()).step_by(2) {
@@ -324,16 +499,8 @@ impl DispersionData {
                 }
                 c[0] + sum
             }
-            Self::Formula6 {
-                wavelength_range,
-                c,
-            } => {
+            Self::Formula6 { c, .. } => {
                 // Gases
-                if wavelength < wavelength_range[0] || wavelength > wavelength_range[1] {
-                    return Err(anyhow!(
-                        "The requested wavelength is outside the range of the available dispersion data."
-                    ));
-                }
 
                 let mut sum = 0.0;
                 for i in (1..c.len()).step_by(2) {
@@ -341,16 +508,8 @@ impl DispersionData {
                 }
                 1.0 + c[0] + sum
             }
-            Self::Formula7 {
-                wavelength_range,
-                c,
-            } => {
+            Self::Formula7 { c, .. } => {
                 // Herzberger
-                if wavelength < wavelength_range[0] || wavelength > wavelength_range[1] {
-                    return Err(anyhow!(
-                        "The requested wavelength is outside the range of the available dispersion data."
-                    ));
-                }
                 let mut sum = 0.0;
                 for i in (3..c.len()).step_by(2) {
                     sum += c[i] * wavelength.powi(i as i32 - 1);
@@ -359,47 +518,164 @@ impl DispersionData {
                     + c[2] / (wavelength.powi(2) - 0.028).powi(2)
                     + sum
             }
-            Self::Formula8 {
-                wavelength_range,
-                c,
-            } => {
+            Self::Formula8 { c, .. } => {
                 // Retro
-                if wavelength < wavelength_range[0] || wavelength > wavelength_range[1] {
-                    return Err(anyhow!(
-                        "The requested wavelength is outside the range of the available dispersion data."
-                    ));
-                }
 
                 let sum = c[0]
                     + c[1] * wavelength.powi(2) / (wavelength.powi(2) - c[2])
                     + c[3] * wavelength.powi(2);
-                println!("sum: {}", sum);
+                log::trace!("Formula 8 sum at {} um: {}", wavelength, sum);
                 ((2.0 * sum + 1.0) / (1.0 - sum)).sqrt()
             }
-            Self::Formula9 {
-                wavelength_range,
-                c,
-            } => {
+            Self::Formula9 { c, .. } => {
                 // Exotic
-                if wavelength < wavelength_range[0] || wavelength > wavelength_range[1] {
-                    return Err(anyhow!(
-                        "The requested wavelength is outside the range of the available dispersion data."
-                    ));
-                }
 
                 (c[0]
                     + c[1] / (wavelength.powi(2) - c[2])
                     + c[3] * (wavelength - c[4]) / ((wavelength - c[4]).powi(2) + c[5]))
                     .sqrt()
             }
-            _ => {
-                return Err(anyhow!("Tabulated dispersion data are not implemented."));
+            Self::TabulatedK { data } => {
+                let k = interpolate_column(data, 1, wavelength, options.interpolation);
+                return Ok((f64::NAN, Some(k)));
+            }
+            Self::TabulatedN { data } => {
+                interpolate_column(data, 1, wavelength, options.interpolation)
+            }
+            Self::TabulatedNK { data } => {
+                let n = interpolate_column(data, 1, wavelength, options.interpolation);
+                let k = interpolate_column(data, 2, wavelength, options.interpolation);
+                return Ok((n, Some(k)));
+            }
+            Self::TabulatedN2 { .. } | Self::Unknown { .. } => {
+                unreachable!("n2 and unknown data are rejected above")
             }
         };
 
         Ok((n, None))
     }
 
+    /// Returns the name of the data type as used by the refractiveindex.info
+    /// database, e.g. "tabulated nk" or "formula 2".
+    pub fn type_name(&self) -> &str {
+        match self {
+            Self::TabulatedK { .. } => "tabulated k",
+            Self::TabulatedN { .. } => "tabulated n",
+            Self::TabulatedNK { .. } => "tabulated nk",
+            Self::TabulatedN2 { .. } => "tabulated n2",
+            Self::Formula1 { .. } => "formula 1",
+            Self::Formula2 { .. } => "formula 2",
+            Self::Formula3 { .. } => "formula 3",
+            Self::Formula4 { .. } => "formula 4",
+            Self::Formula5 { .. } => "formula 5",
+            Self::Formula6 { .. } => "formula 6",
+            Self::Formula7 { .. } => "formula 7",
+            Self::Formula8 { .. } => "formula 8",
+            Self::Formula9 { .. } => "formula 9",
+            Self::Unknown { type_name, .. } => type_name,
+        }
+    }
+
+    /// Returns the range of wavelengths covered by the data.
+    ///
+    /// # Returns
+    /// The minimum and maximum wavelength, or `None` if a table is empty or
+    /// the data are of an unknown type.
+    pub fn wavelength_range(&self) -> Option<[f64; 2]> {
+        let wavelengths: Vec<f64> = match self {
+            Self::TabulatedK { data }
+            | Self::TabulatedN { data }
+            | Self::TabulatedN2 { data, .. } => data.iter().map(|row| row[0]).collect(),
+            Self::TabulatedNK { data } => data.iter().map(|row| row[0]).collect(),
+            Self::Formula1 {
+                wavelength_range, ..
+            }
+            | Self::Formula2 {
+                wavelength_range, ..
+            }
+            | Self::Formula3 {
+                wavelength_range, ..
+            }
+            | Self::Formula4 {
+                wavelength_range, ..
+            }
+            | Self::Formula5 {
+                wavelength_range, ..
+            }
+            | Self::Formula6 {
+                wavelength_range, ..
+            }
+            | Self::Formula7 {
+                wavelength_range, ..
+            }
+            | Self::Formula8 {
+                wavelength_range, ..
+            }
+            | Self::Formula9 {
+                wavelength_range, ..
+            } => return Some(*wavelength_range),
+            Self::Unknown { .. } => return None,
+        };
+        let min = wavelengths.iter().copied().reduce(f64::min)?;
+        let max = wavelengths.iter().copied().reduce(f64::max)?;
+        Some([min, max])
+    }
+
+    /// Returns the number of tabulated points or formula coefficients, or 0
+    /// for data of an unknown type.
+    pub fn len(&self) -> usize {
+        match self {
+            Self::TabulatedK { data }
+            | Self::TabulatedN { data }
+            | Self::TabulatedN2 { data, .. } => data.len(),
+            Self::TabulatedNK { data } => data.len(),
+            Self::Formula1 { c, .. }
+            | Self::Formula2 { c, .. }
+            | Self::Formula3 { c, .. }
+            | Self::Formula4 { c, .. }
+            | Self::Formula5 { c, .. }
+            | Self::Formula6 { c, .. }
+            | Self::Formula7 { c, .. }
+            | Self::Formula8 { c, .. }
+            | Self::Formula9 { c, .. } => c.len(),
+            Self::Unknown { .. } => 0,
+        }
+    }
+
+    /// Returns `true` if there are no tabulated points or formula
+    /// coefficients.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns `true` if the data are a dispersion formula.
+    pub fn is_formula(&self) -> bool {
+        matches!(
+            self,
+            Self::Formula1 { .. }
+                | Self::Formula2 { .. }
+                | Self::Formula3 { .. }
+                | Self::Formula4 { .. }
+                | Self::Formula5 { .. }
+                | Self::Formula6 { .. }
+                | Self::Formula7 { .. }
+                | Self::Formula8 { .. }
+                | Self::Formula9 { .. }
+        )
+    }
+
+    /// Returns `true` if the data are tabulated. Data of an unknown type are
+    /// neither tabulated nor a formula.
+    pub fn is_tabulated(&self) -> bool {
+        matches!(
+            self,
+            Self::TabulatedK { .. }
+                | Self::TabulatedN { .. }
+                | Self::TabulatedNK { .. }
+                | Self::TabulatedN2 { .. }
+        )
+    }
+
     /// Returns the type of data stored in the DispersionData.
     ///
     /// An item may have either one or two dispersion data sets. If there are
@@ -409,6 +685,9 @@ impl DispersionData {
     /// 1. If the DispersionData is TabulatedN, then it's the real part.
     /// 2. If the DispersionData is TabulatedK, then it's the imaginary part.
     /// 3. If the DispersionData is a formula, then it's the real part.
+    /// 4. If the DispersionData is TabulatedN2, then it's neither; it is the
+    ///    nonlinear index.
+    /// 5. If the DispersionData is of an unknown type, then it's neither.
     ///
     /// If there is only one dispersion data set, then we use one additional
     /// rule:
@@ -420,11 +699,109 @@ impl DispersionData {
             self::DispersionData::TabulatedK { data: _ } => DataType::Imaginary,
             self::DispersionData::TabulatedN { data: _ } => DataType::Real,
             self::DispersionData::TabulatedNK { data: _ } => DataType::Both,
+            self::DispersionData::TabulatedN2 { .. } => DataType::Nonlinear,
+            self::DispersionData::Unknown { .. } => DataType::Unsupported,
             _ => DataType::Real,
         }
     }
 }
 
+/// Applies the extrapolation behaviour to a wavelength outside of the range
+/// of some data.
+///
+/// # Returns
+/// The wavelength at which to evaluate the data.
+///
+/// # Errors
+/// - If the wavelength is outside the range and `extrapolation` is
+///   [`Extrapolation::Error`].
+pub(crate) fn extrapolate(
+    wavelength: f64,
+    [min, max]: [f64; 2],
+    extrapolation: Extrapolation,
+) -> Result<f64> {
+    if wavelength >= min && wavelength <= max {
+        return Ok(wavelength);
+    }
+    match extrapolation {
+        Extrapolation::Error => Err(anyhow!(
+            "The requested wavelength is outside the range of the available dispersion data."
+        )),
+        Extrapolation::Clamp => Ok(wavelength.clamp(min, max)),
+        Extrapolation::Extend => Ok(wavelength),
+    }
+}
+
+/// Interpolates one column of a table at the given wavelength.
+///
+/// The first column of each row is the wavelength, and the rows are sorted by
+/// wavelength. Wavelengths outside of the table are extrapolated linearly.
+pub(crate) fn interpolate_column<const N: usize>(
+    rows: &[[f64; N]],
+    column: usize,
+    wavelength: f64,
+    interpolation: Interpolation,
+) -> f64 {
+    let x = |i: usize| rows[i][0];
+    let y = |i: usize| rows[i][column];
+    if rows.len() == 1 {
+        return y(0);
+    }
+
+    // The index of the segment [x(i), x(i + 1)] containing the wavelength
+    let i = rows
+        .partition_point(|row| row[0] <= wavelength)
+        .clamp(1, rows.len() - 1)
+        - 1;
+    let h = x(i + 1) - x(i);
+    let t = (wavelength - x(i)) / h;
+    if interpolation == Interpolation::Linear || !(0.0..=1.0).contains(&t) {
+        return y(i) + t * (y(i + 1) - y(i));
+    }
+
+    // Cubic Hermite interpolation with Fritsch-Butland tangents
+    let slope = |j: usize| (y(j + 1) - y(j)) / (x(j + 1) - x(j));
+    let tangent = |j: usize| {
+        if j == 0 {
+            return slope(0);
+        }
+        if j == rows.len() - 1 {
+            return slope(j - 1);
+        }
+        let (d0, d1) = (slope(j - 1), slope(j));
+        if d0 * d1 <= 0.0 {
+            return 0.0;
+        }
+        let (h0, h1) = (x(j) - x(j - 1), x(j + 1) - x(j));
+        3.0 * (h0 + h1) / ((2.0 * h1 + h0) / d0 + (h1 + 2.0 * h0) / d1)
+    };
+    let (t2, t3) = (t * t, t * t * t);
+    (2.0 * t3 - 3.0 * t2 + 1.0) * y(i)
+        + (t3 - 2.0 * t2 + t) * h * tangent(i)
+        + (-2.0 * t3 + 3.0 * t2) * y(i + 1)
+        + (t3 - t2) * h * tangent(i + 1)
+}
+
+/// An empty material from the nk catalog, so that tests only need to give the
+/// fields they use, e.g. `Material { data, ..Default::default() }`.
+#[cfg(test)]
+impl Default for Material {
+    fn default() -> Self {
+        Material {
+            shelf: String::new(),
+            book: String::new(),
+            page: String::new(),
+            comments: String::new(),
+            references: String::new(),
+            data: Vec::new(),
+            shelf_divider: None,
+            book_divider: None,
+            specs: None,
+            catalog: CatalogKind::Nk,
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -435,11 +812,8 @@ mod test {
             shelf: "main".to_string(),
             book: "Ag (Silver)".to_string(),
             page: "Johnson and Christy 1972".to_string(),
-            comments: "".to_string(),
-            references: "".to_string(),
             data,
-            shelf_divider: None,
-            book_divider: None,
+            ..Default::default()
         }
     }
 
@@ -548,6 +922,26 @@ mod test {
         assert!(material.n(0.55).is_err());
     }
 
+    #[test]
+    fn test_unknown_data_are_skipped() {
+        let unknown = DispersionData::Unknown {
+            type_name: "formula 42".to_string(),
+            raw: "coefficients: 1 2 3\n".to_string(),
+        };
+        assert_eq!(unknown.type_name(), "formula 42");
+        assert!(matches!(unknown.data_type(), DataType::Unsupported));
+        assert!(!unknown.is_tabulated() && !unknown.is_formula());
+        assert!(unknown.wavelength_range().is_none());
+        let err = unknown.interpolate(0.5).unwrap_err();
+        assert!(err.to_string().contains("unsupported type \"formula 42\""));
+
+        let tabulated = DispersionData::TabulatedN {
+            data: vec![[0.5, 1.5], [0.6, 1.6]],
+        };
+        let material = test_material(vec![unknown, tabulated]);
+        assert_abs_diff_eq!(material.n(0.55).unwrap(), 1.55, epsilon = 1e-12);
+    }
+
     #[test]
     fn test_interpolate_wavelength_out_of_range() {
         let data = DispersionData::Formula2 {
@@ -566,6 +960,140 @@ mod test {
         assert!(err.to_string().contains("outside the range"));
     }
 
+    #[test]
+    fn test_interpolate_tabulated_n_linear() {
+        let data = DispersionData::TabulatedN {
+            data: vec![[0.4, 1.5], [0.5, 1.4], [0.7, 1.3]],
+        };
+        let (n, k) = data.interpolate(0.45).unwrap();
+        assert_abs_diff_eq!(n, 1.45, epsilon = 1e-12);
+        assert!(k.is_none());
+        let (n, _) = data.interpolate(0.6).unwrap();
+        assert_abs_diff_eq!(n, 1.35, epsilon = 1e-12);
+        let (n, _) = data.interpolate(0.7).unwrap();
+        assert_abs_diff_eq!(n, 1.3, epsilon = 1e-12);
+    }
+
+    #[test]
+    fn test_interpolate_tabulated_nk() {
+        let data = DispersionData::TabulatedNK {
+            data: vec![[0.4, 0.05, 2.1], [0.5, 0.05, 3.1]],
+        };
+        let (n, k) = data.interpolate(0.45).unwrap();
+        assert_abs_diff_eq!(n, 0.05, epsilon = 1e-12);
+        assert_abs_diff_eq!(k.unwrap(), 2.6, epsilon = 1e-12);
+        assert!(data.interpolate(0.3).is_err());
+    }
+
+    #[test]
+    fn test_material_k_with_tabulated_k() {
+        let material = test_material(vec![
+            DispersionData::Formula2 {
+                wavelength_range: [0.3, 2.5],
+                c: vec![
+                    0.0,
+                    1.03961212,
+                    0.00600069867,
+                    0.231792344,
+                    0.0200179144,
+                    1.01046945,
+                    103.560653,
+                ],
+            },
+            DispersionData::TabulatedK {
+                data: vec![[0.5, 1e-8], [0.6, 3e-8]],
+            },
+        ]);
+        assert_abs_diff_eq!(material.k(0.55).unwrap().unwrap(), 2e-8, epsilon = 1e-20);
+        assert_abs_diff_eq!(material.n(0.5876).unwrap(), 1.51680, epsilon = 1e-5);
+    }
+
+    #[test]
+    fn test_interpolate_tabulated_cubic_is_monotone() {
+        let data = DispersionData::TabulatedN {
+            data: vec![[0.4, 1.0], [0.5, 1.0], [0.6, 2.0], [0.7, 2.0]],
+        };
+        let options = EvalOptions {
+            interpolation: Interpolation::Cubic,
+            ..Default::default()
+        };
+
+        // The curve passes through the points and does not overshoot them
+        let (n, _) = data.interpolate_with(0.6, &options).unwrap();
+        assert_abs_diff_eq!(n, 2.0, epsilon = 1e-12);
+        let (n, _) = data.interpolate_with(0.55, &options).unwrap();
+        assert_abs_diff_eq!(n, 1.5, epsilon = 1e-12);
+        for i in 0..=30 {
+            let (n, _) = data
+                .interpolate_with(0.4 + i as f64 * 0.01, &options)
+                .unwrap();
+            assert!((1.0..=2.0).contains(&n));
+        }
+        let (n, _) = data.interpolate_with(0.65, &options).unwrap();
+        assert_abs_diff_eq!(n, 2.0, epsilon = 1e-12);
+    }
+
+    #[test]
+    fn test_interpolate_extrapolation() {
+        let data = DispersionData::TabulatedN {
+            data: vec![[0.4, 1.5], [0.5, 1.4]],
+        };
+        let clamp = EvalOptions {
+            extrapolation: Extrapolation::Clamp,
+            ..Default::default()
+        };
+        let extend = EvalOptions {
+            extrapolation: Extrapolation::Extend,
+            ..Default::default()
+        };
+        assert!(data.interpolate(0.6).is_err());
+        assert_abs_diff_eq!(
+            data.interpolate_with(0.6, &clamp).unwrap().0,
+            1.4,
+            epsilon = 1e-12
+        );
+        assert_abs_diff_eq!(
+            data.interpolate_with(0.6, &extend).unwrap().0,
+            1.3,
+            epsilon = 1e-12
+        );
+
+        let formula = DispersionData::Formula5 {
+            wavelength_range: [0.31, 1.55],
+            c: vec![1.502787, 455872.4E-8, -2.0, 9.844856E-5, -4.0],
+        };
+        let (at_edge, _) = formula.interpolate(0.31).unwrap();
+        let (clamped, _) = formula.interpolate_with(0.2, &clamp).unwrap();
+        let (extended, _) = formula.interpolate_with(0.2, &extend).unwrap();
+        assert_abs_diff_eq!(clamped, at_edge, epsilon = 1e-12);
+        assert!(extended > at_edge);
+    }
+
+    #[test]
+    fn test_wavelength_range_tabulated() {
+        let data = DispersionData::TabulatedNK {
+            data: vec![[0.4, 0.05, 2.1], [0.5, 0.05, 3.1], [0.7, 0.14, 4.5]],
+        };
+        assert_eq!(data.wavelength_range(), Some([0.4, 0.7]));
+        assert_eq!(data.len(), 3);
+        assert!(data.is_tabulated());
+
+        let empty = DispersionData::TabulatedK { data: vec![] };
+        assert_eq!(empty.wavelength_range(), None);
+    }
+
+    #[test]
+    fn test_wavelength_range_formula() {
+        let data = DispersionData::Formula5 {
+            wavelength_range: [0.31, 1.55],
+            c: vec![1.502787, 455872.4E-8, -2.0, 9.844856E-5, -4.0],
+        };
+        assert_eq!(data.wavelength_range(), Some([0.31, 1.55]));
+        assert_eq!(data.len(), 5);
+        assert!(!data.is_tabulated());
+        assert_eq!(data.type_name(), "formula 5");
+    }
+
     #[test]
     fn test_interpolate_formula_1() {
         // Water Ice at 150 K from refractiveindex.info
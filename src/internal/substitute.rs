@@ -0,0 +1,226 @@
+//! Finding substitutes for a glass by comparing optical properties and
+//! dispersion curves.
+use anyhow::{anyhow, Result};
+
+use crate::internal::combine::key_parts;
+use crate::internal::store::{Material, Store};
+
+/// The differences in nd, Vd and PgF that each count as a distance of 1.
+const ND_SCALE: f64 = 0.01;
+const VD_SCALE: f64 = 1.0;
+const PGF_SCALE: f64 = 0.005;
+
+/// The number of wavelengths at which dispersion curves are compared.
+const CURVE_SAMPLES: usize = 64;
+
+/// A candidate substitute for a glass.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GlassMatch<'a> {
+    pub key: &'a str,
+
+    /// The refractive index at the d line.
+    pub nd: f64,
+
+    /// The Abbe number.
+    pub vd: f64,
+
+    /// The relative partial dispersion, if the data cover the g line.
+    pub pgf: Option<f64>,
+
+    /// The root mean square difference between the refractive indices of
+    /// the two glasses over the range of wavelengths covered by both.
+    pub rms: f64,
+
+    /// The combined distance used to rank the candidates. Differences of
+    /// 0.01 in nd and RMS, 1 in Vd and 0.005 in PgF each count as 1.
+    pub distance: f64,
+}
+
+impl Store {
+    /// Finds the glasses closest to a given glass.
+    ///
+    /// Candidates are the other materials on the same shelf whose nd and Vd
+    /// can be computed. They are ranked by their distance from the glass in
+    /// (nd, Vd, PgF) space combined with the RMS difference of their
+    /// dispersion curves. PgF is ignored if either glass lacks data at the g
+    /// line.
+    ///
+    /// # Arguments
+    /// - `key`: The key of the glass to replace, e.g. glass:BK7:SCHOTT.
+    /// - `n`: The maximum number of glasses to return.
+    ///
+    /// # Returns
+    /// The closest glasses, closest first.
+    ///
+    /// # Errors
+    /// - If the key is not in the store.
+    /// - If nd or Vd of the glass cannot be computed.
+    pub fn nearest_glasses(&self, key: &str, n: usize) -> Result<Vec<GlassMatch<'_>>> {
+        let glass = self
+            .get(key)
+            .ok_or(anyhow!("Key not found in store: {}", key))?;
+        let (nd, vd, pgf) =
+            glass_properties(glass).ok_or(anyhow!("Cannot compute nd and Vd of {}", key))?;
+        let shelf = key_parts(key).next();
+
+        let mut matches: Vec<GlassMatch> = self
+            .iter()
+            .filter(|(other, _)| other.as_str() != key && key_parts(other).next() == shelf)
+            .filter_map(|(other_key, other)| {
+                let (other_nd, other_vd, other_pgf) = glass_properties(other)?;
+                let rms = rms_difference(glass, other)?;
+
+                let mut squares = ((other_nd - nd) / ND_SCALE).powi(2)
+                    + ((other_vd - vd) / VD_SCALE).powi(2)
+                    + (rms / ND_SCALE).powi(2);
+                if let (Some(pgf), Some(other_pgf)) = (pgf, other_pgf) {
+                    squares += ((other_pgf - pgf) / PGF_SCALE).powi(2);
+                }
+
+                Some(GlassMatch {
+                    key: other_key,
+                    nd: other_nd,
+                    vd: other_vd,
+                    pgf: other_pgf,
+                    rms,
+                    distance: squares.sqrt(),
+                })
+            })
+            .collect();
+        matches.sort_by(|a, b| {
+            a.distance
+                .total_cmp(&b.distance)
+                .then_with(|| a.key.cmp(b.key))
+        });
+        matches.truncate(n);
+        Ok(matches)
+    }
+}
+
+/// Returns nd, Vd and, if available, PgF of a material.
+fn glass_properties(material: &Material) -> Option<(f64, f64, Option<f64>)> {
+    let nd = material.nd().ok().filter(|nd| nd.is_finite())?;
+    let vd = material.abbe_number().ok().filter(|vd| vd.is_finite())?;
+    let pgf = material
+        .partial_dispersion_gf()
+        .ok()
+        .filter(|pgf| pgf.is_finite());
+    Some((nd, vd, pgf))
+}
+
+/// Computes the RMS difference of the refractive indices of two materials
+/// over the wavelengths covered by both.
+fn rms_difference(a: &Material, b: &Material) -> Option<f64> {
+    let [a_min, a_max] = a.n_range()?;
+    let [b_min, b_max] = b.n_range()?;
+    let (min, max) = (a_min.max(b_min), a_max.min(b_max));
+    if min > max {
+        return None;
+    }
+
+    let mut sum = 0.0;
+    for i in 0..CURVE_SAMPLES {
+        let wavelength = min + (max - min) * i as f64 / (CURVE_SAMPLES - 1) as f64;
+        sum += (a.n(wavelength).ok()? - b.n(wavelength).ok()?).powi(2);
+    }
+    Some((sum / CURVE_SAMPLES as f64).sqrt())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::DispersionData;
+
+    fn sellmeier_glass(c: Vec<f64>) -> Material {
+        Material {
+            shelf: "glass".to_string(),
+            data: vec![DispersionData::Formula2 {
+                wavelength_range: [0.3, 2.5],
+                c,
+            }],
+            ..Default::default()
+        }
+    }
+
+    fn n_bk7() -> Material {
+        sellmeier_glass(vec![
+            0.0,
+            1.03961212,
+            0.00600069867,
+            0.231792344,
+            0.0200179144,
+            1.01046945,
+            103.560653,
+        ])
+    }
+
+    fn test_store() -> Store {
+        let mut store = Store::default();
+        store.insert("glass:BK7:SCHOTT".to_string(), n_bk7());
+
+        // A glass very close to N-BK7
+        let mut similar = n_bk7();
+        if let DispersionData::Formula2 { c, .. } = &mut similar.data[0] {
+            c[1] += 0.002;
+        }
+        store.insert("glass:BSL7:OHARA".to_string(), similar);
+
+        store.insert(
+            "glass:SF11:SCHOTT".to_string(),
+            sellmeier_glass(vec![
+                0.0,
+                1.73759695,
+                0.013188707,
+                0.313747346,
+                0.0623068142,
+                1.89878101,
+                155.23629,
+            ]),
+        );
+
+        // Materials on other shelves are not candidates
+        store.insert("main:BK7:Copy".to_string(), n_bk7());
+        store
+    }
+
+    #[test]
+    fn test_nearest_glasses_ranking() {
+        let store = test_store();
+        let matches = store.nearest_glasses("glass:BK7:SCHOTT", 10).unwrap();
+        let keys: Vec<&str> = matches.iter().map(|m| m.key).collect();
+        assert_eq!(keys, ["glass:BSL7:OHARA", "glass:SF11:SCHOTT"]);
+        assert!(matches[0].distance < 1.0);
+        assert!(matches[0].rms < 0.01);
+        assert!(matches[0].pgf.is_some());
+        assert!(matches[1].distance > matches[0].distance);
+    }
+
+    #[test]
+    fn test_nearest_glasses_limit() {
+        let store = test_store();
+        let matches = store.nearest_glasses("glass:BK7:SCHOTT", 1).unwrap();
+        assert_eq!(matches.len(), 1);
+    }
+
+    #[test]
+    fn test_nearest_glasses_errors() {
+        let mut store = test_store();
+        assert!(store.nearest_glasses("glass:Missing:SCHOTT", 1).is_err());
+
+        store.insert(
+            "glass:Ag:Johnson".to_string(),
+            Material {
+                data: vec![DispersionData::TabulatedK {
+                    data: vec![[0.4, 0.1], [0.7, 0.2]],
+                }],
+                ..n_bk7()
+            },
+        );
+        assert!(store.nearest_glasses("glass:Ag:Johnson", 1).is_err());
+        // Materials without real data are not candidates
+        assert_eq!(
+            store.nearest_glasses("glass:BK7:SCHOTT", 10).unwrap().len(),
+            2
+        );
+    }
+}
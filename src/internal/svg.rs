@@ -0,0 +1,331 @@
+//! A minimal SVG writer for charts.
+//!
+//! Only the few elements needed for scatter and line plots are supported, so
+//! charts can be drawn without a plotting dependency.
+use std::fmt::Write;
+
+/// The size of a chart in pixels.
+pub(crate) const WIDTH: f64 = 800.0;
+pub(crate) const HEIGHT: f64 = 600.0;
+
+/// The space around the plotting area for tick labels and axis titles.
+pub(crate) const MARGIN_LEFT: f64 = 70.0;
+pub(crate) const MARGIN_RIGHT: f64 = 70.0;
+pub(crate) const MARGIN_TOP: f64 = 40.0;
+pub(crate) const MARGIN_BOTTOM: f64 = 60.0;
+
+/// Colours used for successive series.
+pub(crate) const PALETTE: [&str; 8] = [
+    "#1f77b4", "#d62728", "#2ca02c", "#9467bd", "#ff7f0e", "#17becf", "#8c564b", "#e377c2",
+];
+
+/// Maps data values to pixel coordinates along one axis.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Scale {
+    pub(crate) min: f64,
+    pub(crate) max: f64,
+    pub(crate) start: f64,
+    pub(crate) end: f64,
+    pub(crate) log: bool,
+}
+
+impl Scale {
+    /// Creates a scale from a data range to a pixel range. The pixel range
+    /// may be reversed, e.g. for a y axis or a decreasing x axis.
+    pub(crate) fn new(min: f64, max: f64, start: f64, end: f64) -> Self {
+        // Widen an empty range so that a single value can still be drawn
+        let (min, max) = if min < max {
+            (min, max)
+        } else {
+            let pad = if min == 0.0 { 1.0 } else { min.abs() * 0.05 };
+            (min - pad, max + pad)
+        };
+        Scale {
+            min,
+            max,
+            start,
+            end,
+            log: false,
+        }
+    }
+
+    /// Creates a logarithmic scale. Both ends of the data range must be
+    /// positive.
+    pub(crate) fn log(min: f64, max: f64, start: f64, end: f64) -> Self {
+        let (min, max) = if min < max {
+            (min, max)
+        } else {
+            (min / 10.0, max * 10.0)
+        };
+        Scale {
+            min,
+            max,
+            start,
+            end,
+            log: true,
+        }
+    }
+
+    /// Returns the pixel coordinate of a data value.
+    pub(crate) fn map(&self, value: f64) -> f64 {
+        let t = if self.log {
+            (value.log10() - self.min.log10()) / (self.max.log10() - self.min.log10())
+        } else {
+            (value - self.min) / (self.max - self.min)
+        };
+        self.start + t * (self.end - self.start)
+    }
+
+    /// Returns evenly spaced round values within the data range for tick
+    /// marks, or powers of ten for a logarithmic scale.
+    pub(crate) fn ticks(&self) -> Vec<f64> {
+        if self.log {
+            let first = self.min.log10().ceil() as i32;
+            let last = self.max.log10().floor() as i32;
+            return (first..=last).map(|p| 10f64.powi(p)).collect();
+        }
+
+        let rough = (self.max - self.min) / 6.0;
+        let magnitude = 10f64.powf(rough.log10().floor());
+        let step = [1.0, 2.0, 5.0, 10.0]
+            .into_iter()
+            .map(|m| m * magnitude)
+            .find(|step| *step >= rough)
+            .unwrap_or(10.0 * magnitude);
+
+        let first = (self.min / step).ceil() as i64;
+        let last = (self.max / step).floor() as i64;
+        (first..=last).map(|i| i as f64 * step).collect()
+    }
+}
+
+/// Returns the range of the values padded by 5% on each side, or 0 to 1 if
+/// there are no values.
+pub(crate) fn padded_range(values: impl Iterator<Item = f64>) -> (f64, f64) {
+    let (min, max) = values.fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), v| {
+        (min.min(v), max.max(v))
+    });
+    if min > max {
+        return (0.0, 1.0);
+    }
+    let pad = (max - min) * 0.05;
+    (min - pad, max + pad)
+}
+
+/// Returns the range of the positive values padded by a factor of 1.1 on each
+/// side, for a logarithmic scale, or 0.1 to 1 if there are no positive values.
+pub(crate) fn padded_log_range(values: impl Iterator<Item = f64>) -> (f64, f64) {
+    let (min, max) = values
+        .filter(|v| *v > 0.0)
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), v| {
+            (min.min(v), max.max(v))
+        });
+    if min > max {
+        return (0.1, 1.0);
+    }
+    (min / 1.1, max * 1.1)
+}
+
+/// Formats a tick label without trailing zeros.
+pub(crate) fn tick_label(value: f64) -> String {
+    if value != 0.0 && (value.abs() < 1e-3 || value.abs() >= 1e5) {
+        return format!("{:e}", value);
+    }
+    let label = format!("{:.4}", value);
+    let label = label.trim_end_matches('0').trim_end_matches('.');
+    if label == "-0" {
+        "0".to_string()
+    } else {
+        label.to_string()
+    }
+}
+
+/// Escapes text for use in SVG content and attribute values.
+pub(crate) fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// An SVG document under construction.
+pub(crate) struct Svg {
+    body: String,
+}
+
+impl Svg {
+    pub(crate) fn new() -> Self {
+        Svg {
+            body: String::new(),
+        }
+    }
+
+    /// Draws a frame around the plotting area with tick marks, tick labels
+    /// and a title on the bottom and left axes.
+    pub(crate) fn axes(&mut self, x: &Scale, y: &Scale, x_title: &str, y_title: &str) {
+        let (left, right) = (MARGIN_LEFT, WIDTH - MARGIN_RIGHT);
+        let (top, bottom) = (MARGIN_TOP, HEIGHT - MARGIN_BOTTOM);
+        let _ = writeln!(
+            self.body,
+            r#"<rect x="{left}" y="{top}" width="{}" height="{}" fill="none" stroke="black"/>"#,
+            right - left,
+            bottom - top
+        );
+
+        for tick in x.ticks() {
+            let px = x.map(tick);
+            self.line(px, bottom, px, bottom + 5.0, "black");
+            self.text(px, bottom + 20.0, "middle", &tick_label(tick), None);
+        }
+        self.text((left + right) / 2.0, HEIGHT - 15.0, "middle", x_title, None);
+
+        self.y_ticks(y, left, -1.0);
+        let _ = writeln!(
+            self.body,
+            r#"<text x="20" y="{0}" text-anchor="middle" transform="rotate(-90 20 {0})">{1}</text>"#,
+            (top + bottom) / 2.0,
+            escape(y_title)
+        );
+    }
+
+    /// Draws tick marks, tick labels and a title on the right axis.
+    pub(crate) fn right_axis(&mut self, y: &Scale, title: &str, color: &str) {
+        self.y_ticks(y, WIDTH - MARGIN_RIGHT, 1.0);
+        let x = WIDTH - 15.0;
+        let _ = writeln!(
+            self.body,
+            r#"<text x="{x}" y="{0}" text-anchor="middle" fill="{color}" transform="rotate(90 {x} {0})">{1}</text>"#,
+            (MARGIN_TOP + HEIGHT - MARGIN_BOTTOM) / 2.0,
+            escape(title)
+        );
+    }
+
+    /// Draws ticks on a vertical axis at `x`, pointing outward in the
+    /// direction of `side`.
+    fn y_ticks(&mut self, y: &Scale, x: f64, side: f64) {
+        let anchor = if side < 0.0 { "end" } else { "start" };
+        for tick in y.ticks() {
+            let py = y.map(tick);
+            self.line(x, py, x + 5.0 * side, py, "black");
+            self.text(x + 8.0 * side, py + 4.0, anchor, &tick_label(tick), None);
+        }
+    }
+
+    pub(crate) fn line(&mut self, x1: f64, y1: f64, x2: f64, y2: f64, stroke: &str) {
+        let _ = writeln!(
+            self.body,
+            r#"<line x1="{x1:.2}" y1="{y1:.2}" x2="{x2:.2}" y2="{y2:.2}" stroke="{stroke}"/>"#
+        );
+    }
+
+    /// Draws a line through the points, with an optional dash pattern.
+    pub(crate) fn polyline(&mut self, points: &[(f64, f64)], stroke: &str, dash: Option<&str>) {
+        let points: Vec<String> = points
+            .iter()
+            .map(|(x, y)| format!("{x:.2},{y:.2}"))
+            .collect();
+        let dash = dash
+            .map(|dash| format!(r#" stroke-dasharray="{dash}""#))
+            .unwrap_or_default();
+        let _ = writeln!(
+            self.body,
+            r#"<polyline points="{}" fill="none" stroke="{stroke}" stroke-width="1.5"{dash}/>"#,
+            points.join(" ")
+        );
+    }
+
+    /// Draws a filled circle with an optional tooltip.
+    pub(crate) fn circle(&mut self, x: f64, y: f64, r: f64, fill: &str, title: Option<&str>) {
+        match title {
+            Some(title) => {
+                let _ = writeln!(
+                    self.body,
+                    r#"<circle cx="{x:.2}" cy="{y:.2}" r="{r}" fill="{fill}"><title>{}</title></circle>"#,
+                    escape(title)
+                );
+            }
+            None => {
+                let _ = writeln!(
+                    self.body,
+                    r#"<circle cx="{x:.2}" cy="{y:.2}" r="{r}" fill="{fill}"/>"#
+                );
+            }
+        }
+    }
+
+    /// Draws text anchored at `start`, `middle` or `end`.
+    pub(crate) fn text(&mut self, x: f64, y: f64, anchor: &str, text: &str, fill: Option<&str>) {
+        let fill = fill
+            .map(|fill| format!(r#" fill="{fill}""#))
+            .unwrap_or_default();
+        let _ = writeln!(
+            self.body,
+            r#"<text x="{x:.2}" y="{y:.2}" text-anchor="{anchor}"{fill}>{}</text>"#,
+            escape(text)
+        );
+    }
+
+    /// Returns the complete document.
+    pub(crate) fn finish(self) -> String {
+        format!(
+            concat!(
+                r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{1}" viewBox="0 0 {0} {1}" font-family="sans-serif" font-size="12">"#,
+                "\n",
+                r#"<rect width="100%" height="100%" fill="white"/>"#,
+                "\n{2}</svg>\n"
+            ),
+            WIDTH, HEIGHT, self.body
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use approx::assert_abs_diff_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_scale_map() {
+        let scale = Scale::new(0.0, 10.0, 100.0, 0.0);
+        assert_eq!(scale.map(0.0), 100.0);
+        assert_eq!(scale.map(2.5), 75.0);
+
+        let scale = Scale::log(0.01, 10.0, 0.0, 300.0);
+        assert_abs_diff_eq!(scale.map(0.1), 100.0, epsilon = 1e-9);
+    }
+
+    #[test]
+    fn test_scale_ticks() {
+        let ticks = Scale::new(0.4, 0.8, 0.0, 1.0).ticks();
+        assert_eq!(ticks.len(), 5);
+        assert_abs_diff_eq!(ticks[4], 0.8, epsilon = 1e-12);
+        let ticks = Scale::new(20.0, 90.0, 0.0, 1.0).ticks();
+        assert_eq!(ticks, [20.0, 40.0, 60.0, 80.0]);
+        assert_eq!(Scale::log(0.05, 20.0, 0.0, 1.0).ticks(), [0.1, 1.0, 10.0]);
+    }
+
+    #[test]
+    fn test_padded_log_range() {
+        let (min, max) = padded_log_range([0.01, 1.0, 4.0].into_iter());
+        assert_abs_diff_eq!(min, 0.01 / 1.1);
+        assert_abs_diff_eq!(max, 4.4);
+        assert_eq!(padded_log_range([0.0, -1.0].into_iter()), (0.1, 1.0));
+    }
+
+    #[test]
+    fn test_tick_label() {
+        assert_eq!(tick_label(1.5), "1.5");
+        assert_eq!(tick_label(20.0), "20");
+        assert_eq!(tick_label(-0.0), "0");
+        assert_eq!(tick_label(0.0001), "1e-4");
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(
+            escape("Al₂O₃ <\"o\"> & e"),
+            "Al₂O₃ &lt;&quot;o&quot;&gt; &amp; e"
+        );
+    }
+}
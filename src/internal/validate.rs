@@ -0,0 +1,559 @@
+//! Semantic checks of the dispersion data of materials.
+use std::fmt;
+
+use crate::internal::store::{DispersionData, Material, Store};
+
+/// The number of wavelengths at which formulas are evaluated.
+const FORMULA_SAMPLES: usize = 32;
+
+/// The largest differences between computed values and the values in a
+/// material's SPECS that are accepted.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpecsTolerance {
+    /// The tolerance of nd.
+    pub nd: f64,
+
+    /// The tolerance of the Abbe number Vd.
+    pub vd: f64,
+}
+
+impl Default for SpecsTolerance {
+    /// Catalogs round nd to four or five decimals and Vd to two.
+    fn default() -> Self {
+        SpecsTolerance { nd: 2e-4, vd: 0.2 }
+    }
+}
+
+/// How serious a validation issue is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// The data are usable but probably not what was intended, e.g. a table
+    /// with a single row.
+    Warning,
+
+    /// The data are invalid, e.g. a formula evaluates to NaN.
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// A problem found in the data of a material.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Issue {
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl Issue {
+    fn error(message: String) -> Issue {
+        Issue {
+            severity: Severity::Error,
+            message,
+        }
+    }
+
+    fn warning(message: String) -> Issue {
+        Issue {
+            severity: Severity::Warning,
+            message,
+        }
+    }
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.severity, self.message)
+    }
+}
+
+impl Material {
+    /// Checks that the dispersion data of the material are valid.
+    ///
+    /// The following are errors:
+    /// - Wavelength ranges that are not positive and ordered.
+    /// - Tabulated wavelengths that are not strictly increasing.
+    /// - NaN or infinite wavelengths, values or coefficients.
+    /// - Negative values of k.
+    /// - Empty tables and formulas with the wrong number of coefficients.
+    /// - Formulas for which n is not a finite real number at sample points
+    ///   across their range.
+    ///
+    /// Materials without data and tables with a single row are warnings.
+    ///
+    /// # Returns
+    /// The issues found, each prefixed with the position and type of the data
+    /// it concerns.
+    pub fn validate(&self) -> Vec<Issue> {
+        if self.data.is_empty() {
+            return vec![Issue::warning("The material has no data".to_string())];
+        }
+
+        let mut issues = Vec::new();
+        for (i, data) in self.data.iter().enumerate() {
+            let prefix = format!("Data {} ({})", i + 1, data.type_name());
+            issues.extend(validate_data(data).into_iter().map(|issue| Issue {
+                message: format!("{}: {}", prefix, issue.message),
+                ..issue
+            }));
+        }
+        issues
+    }
+
+    /// Compares nd and Vd computed from the dispersion data with the values
+    /// published in the material's SPECS.
+    ///
+    /// Values that differ by more than the tolerance are errors, as they
+    /// point to wrong coefficients or a wrong implementation of a formula.
+    /// Values that cannot be computed, e.g. because the data do not cover the
+    /// F and C lines, are warnings.
+    ///
+    /// # Arguments
+    /// - `tolerance`: The largest differences that are accepted.
+    ///
+    /// # Returns
+    /// The issues found. Materials without SPECS have none.
+    pub fn check_specs(&self, tolerance: &SpecsTolerance) -> Vec<Issue> {
+        let Some(specs) = &self.specs else {
+            return Vec::new();
+        };
+
+        let checks = [
+            ("nd", specs.nd, self.nd(), tolerance.nd),
+            ("Vd", specs.vd, self.abbe_number(), tolerance.vd),
+        ];
+        let mut issues = Vec::new();
+        for (name, expected, computed, tolerance) in checks {
+            let Some(expected) = expected else { continue };
+            match computed {
+                Ok(computed) if (computed - expected).abs() <= tolerance => {}
+                Ok(computed) => issues.push(Issue::error(format!(
+                    "{} is {:.5}, but the SPECS give {} (difference {:.2e})",
+                    name,
+                    computed,
+                    expected,
+                    computed - expected
+                ))),
+                Err(e) => issues.push(Issue::warning(format!(
+                    "{} cannot be computed to compare with the SPECS: {}",
+                    name, e
+                ))),
+            }
+        }
+        issues
+    }
+}
+
+impl Store {
+    /// Checks the dispersion data of every material in the store.
+    ///
+    /// See [`Material::validate`] for the checks that are made.
+    ///
+    /// # Returns
+    /// The keys of the materials with issues and their issues, sorted by key.
+    pub fn validate(&self) -> Vec<(&str, Vec<Issue>)> {
+        self.report(Material::validate)
+    }
+
+    /// Compares nd and Vd of every material with SPECS with the published
+    /// values.
+    ///
+    /// See [`Material::check_specs`].
+    ///
+    /// # Arguments
+    /// - `tolerance`: The largest differences that are accepted.
+    ///
+    /// # Returns
+    /// The keys of the materials with issues and their issues, sorted by key.
+    pub fn check_specs(&self, tolerance: &SpecsTolerance) -> Vec<(&str, Vec<Issue>)> {
+        self.report(|material| material.check_specs(tolerance))
+    }
+
+    /// Runs a check on every material and collects the materials with issues.
+    fn report(&self, check: impl Fn(&Material) -> Vec<Issue>) -> Vec<(&str, Vec<Issue>)> {
+        let mut report: Vec<(&str, Vec<Issue>)> = self
+            .iter()
+            .map(|(key, material)| (key.as_str(), check(material)))
+            .filter(|(_, issues)| !issues.is_empty())
+            .collect();
+        report.sort_by_key(|(key, _)| *key);
+        report
+    }
+}
+
+/// Checks a single set of dispersion data.
+fn validate_data(data: &DispersionData) -> Vec<Issue> {
+    match data {
+        DispersionData::TabulatedK { data } => {
+            validate_table(data.iter().map(|row| row.as_slice()), Some(1))
+        }
+        // n2 may be negative, so its column is checked like n
+        DispersionData::TabulatedN { data } | DispersionData::TabulatedN2 { data, .. } => {
+            validate_table(data.iter().map(|row| row.as_slice()), None)
+        }
+        DispersionData::TabulatedNK { data } => {
+            validate_table(data.iter().map(|row| row.as_slice()), Some(2))
+        }
+        DispersionData::Formula1 {
+            wavelength_range,
+            c,
+        }
+        | DispersionData::Formula2 {
+            wavelength_range,
+            c,
+        }
+        | DispersionData::Formula3 {
+            wavelength_range,
+            c,
+        }
+        | DispersionData::Formula4 {
+            wavelength_range,
+            c,
+        }
+        | DispersionData::Formula5 {
+            wavelength_range,
+            c,
+        }
+        | DispersionData::Formula6 {
+            wavelength_range,
+            c,
+        }
+        | DispersionData::Formula7 {
+            wavelength_range,
+            c,
+        }
+        | DispersionData::Formula8 {
+            wavelength_range,
+            c,
+        }
+        | DispersionData::Formula9 {
+            wavelength_range,
+            c,
+        } => validate_formula(data, *wavelength_range, c),
+        DispersionData::Unknown { .. } => vec![Issue::warning(
+            "The data type is not supported, so the data are kept but cannot be evaluated"
+                .to_string(),
+        )],
+    }
+}
+
+/// Checks the rows of a table, whose first column is the wavelength.
+///
+/// # Arguments
+/// - `rows`: The rows of the table.
+/// - `k_column`: The column of k, if any, which must not be negative.
+fn validate_table<'a>(
+    rows: impl ExactSizeIterator<Item = &'a [f64]>,
+    k_column: Option<usize>,
+) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    match rows.len() {
+        0 => return vec![Issue::error("The table is empty".to_string())],
+        1 => issues.push(Issue::warning(
+            "The table has a single row, so it cannot be interpolated".to_string(),
+        )),
+        _ => {}
+    }
+
+    let mut previous: Option<f64> = None;
+    for (i, row) in rows.enumerate() {
+        let line = i + 1;
+        if let Some(value) = row.iter().find(|value| !value.is_finite()) {
+            issues.push(Issue::error(format!(
+                "Row {} contains the non-finite value {}",
+                line, value
+            )));
+            continue;
+        }
+
+        let wavelength = row[0];
+        if wavelength <= 0.0 {
+            issues.push(Issue::error(format!(
+                "Row {} has the non-positive wavelength {}",
+                line, wavelength
+            )));
+        }
+        if let Some(previous) = previous.filter(|previous| wavelength <= *previous) {
+            issues.push(Issue::error(format!(
+                "Row {} has the wavelength {}, which does not increase from {}",
+                line, wavelength, previous
+            )));
+        }
+        previous = Some(wavelength);
+
+        if let Some(k) = k_column.map(|column| row[column]).filter(|k| *k < 0.0) {
+            issues.push(Issue::error(format!(
+                "Row {} has the negative k {}",
+                line, k
+            )));
+        }
+    }
+    issues
+}
+
+/// Checks the range and coefficients of a formula and evaluates it across
+/// its range.
+fn validate_formula(data: &DispersionData, [min, max]: [f64; 2], c: &[f64]) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    if !min.is_finite() || !max.is_finite() {
+        issues.push(Issue::error(format!(
+            "The wavelength range {}-{} is not finite",
+            min, max
+        )));
+    } else if min <= 0.0 || min >= max {
+        issues.push(Issue::error(format!(
+            "The wavelength range {}-{} is not positive and ordered",
+            min, max
+        )));
+    }
+    if let Some(value) = c.iter().find(|value| !value.is_finite()) {
+        issues.push(Issue::error(format!(
+            "The coefficients contain the non-finite value {}",
+            value
+        )));
+    }
+    if let Some(expected) = coefficient_count_error(data, c.len()) {
+        issues.push(Issue::error(format!(
+            "There are {} coefficients, but {}",
+            c.len(),
+            expected
+        )));
+    }
+
+    // Only formulas that can be evaluated without panicking are sampled
+    if !issues.is_empty() {
+        return issues;
+    }
+    for i in 0..FORMULA_SAMPLES {
+        let wavelength = min + (max - min) * i as f64 / (FORMULA_SAMPLES - 1) as f64;
+        match data.interpolate(wavelength) {
+            Ok((n, _)) if n.is_finite() => {}
+            Ok((n, _)) => {
+                issues.push(Issue::error(format!("n is {} at {} µm", n, wavelength)));
+                break;
+            }
+            Err(e) => {
+                issues.push(Issue::error(format!(
+                    "n cannot be computed at {} µm: {}",
+                    wavelength, e
+                )));
+                break;
+            }
+        }
+    }
+    issues
+}
+
+/// Checks the number of coefficients of a formula against the coefficients
+/// that evaluating it reads.
+///
+/// # Returns
+/// A description of the expected number of coefficients, or `None` if the
+/// count is valid.
+fn coefficient_count_error(data: &DispersionData, count: usize) -> Option<&'static str> {
+    match data {
+        // A constant followed by pairs of coefficients
+        DispersionData::Formula1 { .. }
+        | DispersionData::Formula2 { .. }
+        | DispersionData::Formula3 { .. }
+        | DispersionData::Formula5 { .. }
+        | DispersionData::Formula6 { .. } => count
+            .is_multiple_of(2)
+            .then_some("formulas 1, 2, 3, 5 and 6 need a constant followed by pairs"),
+        DispersionData::Formula4 { .. } => {
+            // A constant, terms of four coefficients up to c[9], then pairs
+            let complete = count > 0
+                && (1..count)
+                    .step_by(4)
+                    .all(|i| i + if i <= 9 { 3 } else { 1 } < count);
+            (!complete).then_some("formula 4 needs terms of four and then two coefficients")
+        }
+        DispersionData::Formula7 { .. } => (count < 3).then_some("formula 7 needs at least 3"),
+        DispersionData::Formula8 { .. } => (count < 4).then_some("formula 8 needs at least 4"),
+        DispersionData::Formula9 { .. } => (count < 6).then_some("formula 9 needs at least 6"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::internal::store::Specs;
+
+    fn test_material(data: Vec<DispersionData>) -> Material {
+        Material {
+            data,
+            ..Default::default()
+        }
+    }
+
+    fn bk7(wavelength_range: [f64; 2]) -> DispersionData {
+        DispersionData::Formula2 {
+            wavelength_range,
+            c: vec![
+                0.0,
+                1.03961212,
+                0.00600069867,
+                0.231792344,
+                0.0200179144,
+                1.01046945,
+                103.560653,
+            ],
+        }
+    }
+
+    fn messages(material: &Material) -> Vec<String> {
+        material
+            .validate()
+            .iter()
+            .map(|issue| issue.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_valid_material() {
+        let material = test_material(vec![
+            bk7([0.3, 2.5]),
+            DispersionData::TabulatedK {
+                data: vec![[0.3, 1e-6], [2.5, 1e-4]],
+            },
+        ]);
+        assert!(material.validate().is_empty());
+    }
+
+    #[test]
+    fn test_table_issues() {
+        let material = test_material(vec![DispersionData::TabulatedNK {
+            data: vec![
+                [0.5, 1.5, 0.0],
+                [0.4, 1.5, -0.1],
+                [0.6, f64::NAN, 0.0],
+                [-0.7, 1.5, 0.0],
+            ],
+        }]);
+        assert_eq!(
+            messages(&material),
+            [
+                "error: Data 1 (tabulated nk): Row 2 has the wavelength 0.4, which does not increase from 0.5",
+                "error: Data 1 (tabulated nk): Row 2 has the negative k -0.1",
+                "error: Data 1 (tabulated nk): Row 3 contains the non-finite value NaN",
+                "error: Data 1 (tabulated nk): Row 4 has the non-positive wavelength -0.7",
+                "error: Data 1 (tabulated nk): Row 4 has the wavelength -0.7, which does not increase from 0.4",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_warnings() {
+        let material = test_material(vec![]);
+        assert_eq!(messages(&material), ["warning: The material has no data"]);
+
+        let material = test_material(vec![DispersionData::TabulatedN {
+            data: vec![[0.5, 1.5]],
+        }]);
+        let issues = material.validate();
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].severity, Severity::Warning);
+    }
+
+    #[test]
+    fn test_formula_issues() {
+        let material = test_material(vec![bk7([2.5, 0.3])]);
+        assert_eq!(
+            messages(&material),
+            ["error: Data 1 (formula 2): The wavelength range 2.5-0.3 is not positive and ordered"]
+        );
+
+        let material = test_material(vec![DispersionData::Formula1 {
+            wavelength_range: [0.3, 2.5],
+            c: vec![0.0, 1.0],
+        }]);
+        assert!(messages(&material)[0].contains("There are 2 coefficients"));
+
+        let material = test_material(vec![DispersionData::Formula9 {
+            wavelength_range: [0.3, 2.5],
+            c: vec![1.0; 5],
+        }]);
+        assert!(messages(&material)[0].contains("formula 9 needs at least 6"));
+
+        // n² is negative, so n is imaginary
+        let material = test_material(vec![DispersionData::Formula3 {
+            wavelength_range: [0.3, 2.5],
+            c: vec![-2.0, 1.0, 1.0],
+        }]);
+        assert_eq!(
+            messages(&material),
+            ["error: Data 1 (formula 3): n is NaN at 0.3 µm"]
+        );
+    }
+
+    #[test]
+    fn test_formula_4_coefficient_counts() {
+        let formula = |count| DispersionData::Formula4 {
+            wavelength_range: [0.3, 2.5],
+            c: vec![0.0; count],
+        };
+        let valid: Vec<usize> = (0..20)
+            .filter(|&count| coefficient_count_error(&formula(count), count).is_none())
+            .collect();
+        assert_eq!(valid, [1, 5, 9, 13, 15, 16, 17, 19]);
+    }
+
+    #[test]
+    fn test_check_specs() {
+        let tolerance = SpecsTolerance::default();
+        let mut material = test_material(vec![bk7([0.3, 2.5])]);
+        assert!(material.check_specs(&tolerance).is_empty());
+
+        material.specs = Some(Specs {
+            nd: Some(1.5168),
+            vd: Some(64.17),
+        });
+        assert!(material.check_specs(&tolerance).is_empty());
+
+        material.specs = Some(Specs {
+            nd: Some(1.5268),
+            vd: Some(64.17),
+        });
+        let issues = material.check_specs(&tolerance);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].severity, Severity::Error);
+        assert!(issues[0]
+            .message
+            .starts_with("nd is 1.51680, but the SPECS give 1.5268"));
+
+        // The data do not cover the F line
+        let mut material = test_material(vec![bk7([0.5, 2.5])]);
+        material.specs = Some(Specs {
+            nd: Some(1.5168),
+            vd: Some(64.17),
+        });
+        let issues = material.check_specs(&tolerance);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].severity, Severity::Warning);
+        assert!(issues[0].message.starts_with("Vd cannot be computed"));
+    }
+
+    #[test]
+    fn test_store_report() {
+        let mut store = Store::default();
+        store.insert(
+            "glass:BK7:SCHOTT".to_string(),
+            test_material(vec![bk7([0.3, 2.5])]),
+        );
+        store.insert("main:Empty:Page".to_string(), test_material(vec![]));
+        store.insert(
+            "glass:Bad:Page".to_string(),
+            test_material(vec![bk7([0.0, 2.5])]),
+        );
+        let report = store.validate();
+        let keys: Vec<&str> = report.iter().map(|(key, _)| *key).collect();
+        assert_eq!(keys, ["glass:Bad:Page", "main:Empty:Page"]);
+    }
+}
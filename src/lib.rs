@@ -2,9 +2,34 @@
 //!
 //! An adapter for converting the RefractiveIndex.INFO database into a flat,
 //! key-value store.
+//!
+//! Reading and writing stores is enabled with the `json` and `bitcode` cargo
+//! features. Neither requires the `cli` feature. The `mmap` feature adds
+//! memory-mapping of indexed stores, and the `gzip` and `zstd` features add
+//! compressed stores. The `regex` feature allows regular expressions in key
+//! lists.
 
 #[cfg(feature = "cli")]
 pub mod database;
 mod internal;
 
-pub use internal::store::{DispersionData, Material, Store};
+pub use internal::combine::{n2_key_prefix, N2_KEY_PREFIX};
+#[cfg(feature = "bitcode")]
+pub use internal::compact::TablePrecision;
+pub use internal::compression::{compress, decompress, Compression};
+pub use internal::filter::Filter;
+pub use internal::find::{Constraint, Property};
+pub use internal::glass_map::{GlassMap, GlassMapPoint};
+#[cfg(feature = "bitcode")]
+pub use internal::indexed::IndexedStore;
+pub use internal::io::StoreFormat;
+pub use internal::keys::KeyList;
+#[cfg(any(feature = "json", feature = "bitcode"))]
+pub use internal::migrate::StoreVersion;
+pub use internal::optics::{C_LINE, D_LINE, F_LINE, G_LINE};
+pub use internal::plot::PlotOptions;
+pub use internal::store::{
+    CatalogKind, DispersionData, EvalOptions, Extrapolation, Interpolation, Material, Specs, Store,
+};
+pub use internal::substitute::GlassMatch;
+pub use internal::validate::{Issue, Severity, SpecsTolerance};
@@ -1,27 +1,163 @@
-use std::io::{BufRead, Read, Write};
-use std::path::PathBuf;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
 
 use anyhow::Result;
-use clap::{Parser, Subcommand, ValueEnum};
+use clap::{ArgAction, Parser, Subcommand, ValueEnum};
+use log::{debug, info, warn, Level, LevelFilter, Log, Metadata, Record};
+use serde::{Deserialize, Serialize};
 
 use lib_ria::database::Catalog;
-use lib_ria::Store;
+use lib_ria::{
+    compress, decompress, n2_key_prefix, CatalogKind, Compression, Constraint, DispersionData,
+    EvalOptions, Extrapolation, Filter, IndexedStore, Interpolation, Issue, KeyList, Material,
+    PlotOptions, Property, Severity, SpecsTolerance, Store, StoreFormat, StoreVersion,
+    TablePrecision,
+};
+use memmap2::Mmap;
 
 fn main() -> Result<()> {
     let args = Args::parse();
+    Logger::init(args.log_level(), args.log_format)?;
 
     match args.command {
+        Commands::Store {
+            path,
+            config: Some(config),
+            ..
+        } => {
+            store_from_config(args.format.as_ref(), path.as_deref(), &config)?;
+        }
         Commands::Store {
             path,
             catalog,
             output,
-            include,
-            exclude,
+            config: None,
+            select_args,
+            output_args,
         } => {
-            store(&args.format, &path, catalog, &output, include, exclude)?;
+            store(
+                args.format.as_ref().unwrap_or(&Format::Json),
+                &path.unwrap_or_else(|| PathBuf::from(DEFAULT_DATABASE_PATH)),
+                catalog,
+                &output,
+                &select_args,
+                &output_args,
+            )?;
         }
-        Commands::Validate { input } => {
-            validate(&args.format, &input)?;
+        Commands::Validate {
+            input,
+            fail_on,
+            specs_args,
+        } => {
+            validate(args.format.as_ref(), &input, fail_on, &specs_args)?;
+        }
+        Commands::Migrate {
+            input,
+            output,
+            output_args,
+        } => {
+            migrate(args.format.as_ref(), &input, &output, &output_args)?;
+        }
+        Commands::Convert {
+            from,
+            to,
+            input,
+            output,
+            select_args,
+            output_args,
+        } => {
+            convert(
+                from.as_ref(),
+                &to,
+                &input,
+                &output,
+                &select_args,
+                &output_args,
+            )?;
+        }
+        Commands::Get { key, input, json } => {
+            get(args.format.as_ref(), &key, &input, json)?;
+        }
+        Commands::List {
+            input,
+            filter_args,
+            keys_only,
+        } => {
+            list(args.format.as_ref(), &input, &filter_args, keys_only)?;
+        }
+        Commands::Search {
+            text,
+            input,
+            fuzzy,
+            limit,
+            keys_only,
+        } => {
+            search(args.format.as_ref(), &text, &input, fuzzy, limit, keys_only)?;
+        }
+        Commands::Find {
+            input,
+            targets,
+            limit,
+            keys_only,
+        } => {
+            find(args.format.as_ref(), &input, &targets, limit, keys_only)?;
+        }
+        Commands::Substitutes {
+            key,
+            input,
+            count,
+            keys_only,
+        } => {
+            substitutes(args.format.as_ref(), &key, &input, count, keys_only)?;
+        }
+        Commands::GlassMap {
+            input,
+            output,
+            output_format,
+            shelf,
+            highlight,
+        } => {
+            glass_map(
+                args.format.as_ref(),
+                &input,
+                output.as_ref(),
+                output_format,
+                shelf.as_deref(),
+                &highlight,
+            )?;
+        }
+        Commands::Plot {
+            keys,
+            input,
+            output,
+            log_k,
+        } => {
+            plot(args.format.as_ref(), &keys, &input, &output, log_k)?;
+        }
+        Commands::Eval {
+            key,
+            input,
+            grid,
+            units,
+            output_format,
+            interpolation,
+            extrapolation,
+        } => {
+            let options = EvalOptions {
+                interpolation: interpolation.into(),
+                extrapolation: extrapolation.into(),
+            };
+            eval(
+                args.format.as_ref(),
+                &key,
+                &input,
+                &grid,
+                units,
+                output_format,
+                &options,
+            )?;
         }
     }
     Ok(())
@@ -29,24 +165,89 @@ fn main() -> Result<()> {
 
 fn store(
     format: &Format,
-    path: &PathBuf,
+    path: &Path,
     catalog_choice: CatalogChoice,
-    output: &PathBuf,
-    include: Option<PathBuf>,
-    exclude: Option<PathBuf>,
+    output: &Path,
+    select_args: &SelectArgs,
+    output_args: &OutputArgs,
 ) -> Result<()> {
+    output_args.check(format)?;
+    let mut store = read_catalog(path, catalog_choice)?;
+    select_args.select(&mut store)?;
+
+    // Write the store to the output file
+    info!("Writing store to {}", describe(output));
+    save(format, output_args, &store, output)
+}
+
+/// Builds every store described by a configuration file. Each catalog is
+/// read from the database only once. A database path given on the command
+/// line takes precedence over the one in the file.
+fn store_from_config(format: Option<&Format>, path: Option<&Path>, config: &Path) -> Result<()> {
+    info!("Reading the store configuration in {}", config.display());
+    let text = std::fs::read_to_string(config)?;
+    let config_dir = config.parent().unwrap_or(Path::new(""));
+    let config: StoreConfig = toml::from_str(&text)
+        .map_err(|err| anyhow::anyhow!("Invalid configuration {}: {}", config.display(), err))?;
+    config.check(config_dir)?;
+    for output_config in &config.outputs {
+        let (_, output_args) = output_config.args(config_dir);
+        output_args.check(output_config.format(format))?;
+    }
+
+    let path = match (path, &config.path) {
+        (Some(path), _) => path.to_path_buf(),
+        (None, Some(path)) => config_dir.join(path),
+        (None, None) => PathBuf::from(DEFAULT_DATABASE_PATH),
+    };
+    let mut catalogs: HashMap<CatalogChoice, Store> = HashMap::new();
+    for output_config in &config.outputs {
+        let catalog = output_config.catalog;
+        let mut store = match catalogs.entry(catalog) {
+            Entry::Occupied(entry) => entry.get().clone(),
+            Entry::Vacant(entry) => entry.insert(read_catalog(&path, catalog)?).clone(),
+        };
+        let (select_args, output_args) = output_config.args(config_dir);
+        select_args.select(&mut store)?;
+
+        let output = output_config.output(config_dir);
+        let format = output_config.format(format);
+        info!(
+            "Writing {} materials to {} as {}",
+            store.keys().count(),
+            describe(&output),
+            format
+        );
+        save(format, &output_args, &store, &output)?;
+    }
+    Ok(())
+}
+
+/// Reads a catalog of the database into a store, or both catalogs into a
+/// combined store.
+fn read_catalog(path: &Path, catalog_choice: CatalogChoice) -> Result<Store> {
+    match catalog_choice {
+        CatalogChoice::N2 => read_catalog_file(path, CatalogKind::N2),
+        CatalogChoice::NK => read_catalog_file(path, CatalogKind::Nk),
+        CatalogChoice::All => Ok(Store::combine(
+            read_catalog_file(path, CatalogKind::Nk)?,
+            read_catalog_file(path, CatalogKind::N2)?,
+        )),
+    }
+}
+
+/// Reads the materials of one catalog file of the database.
+fn read_catalog_file(path: &Path, kind: CatalogKind) -> Result<Store> {
     // Save the current directory
     let current_dir = std::env::current_dir()?;
 
     // Change the current directory to the database path
-    println!("Changing directory to {}", path.display());
+    debug!("Changing directory to {}", path.display());
     std::env::set_current_dir(path)?;
 
-    // Open the file specified in the args
-    let file = match catalog_choice {
-        CatalogChoice::N2 => std::fs::File::open("catalog-n2.yml")?,
-        CatalogChoice::NK => std::fs::File::open("catalog-nk.yml")?,
-    };
+    // Open the file of the catalog
+    info!("Reading the {} catalog", kind);
+    let file = std::fs::File::open(format!("catalog-{}.yml", kind))?;
     let reader = std::io::BufReader::new(file);
 
     // Deserialize the catalog YAML file
@@ -54,115 +255,1736 @@ fn store(
 
     // Parse the catalog into this library's internal representation
     std::env::set_current_dir("data")?;
-    let mut store = Store::try_from(catalog)?;
+    let store = Store::from_catalog(catalog, kind)?;
 
-    println!("Changing directory back to {}", &current_dir.display());
+    debug!("Changing directory back to {}", current_dir.display());
     std::env::set_current_dir(current_dir)?;
-    let file = std::fs::File::create(output)?;
-
-    // Filter the store if necessary. Exclude is ignored if include is provided.
-    if let Some(include) = include {
-        println!("Filtering store keys using {}", include.display());
-        let file = std::fs::File::open(include)?;
-        let reader = std::io::BufReader::new(file);
-        let keys: Vec<String> = reader.lines().collect::<Result<_, _>>()?;
-        store.retain(|key, _| keys.contains(key));
-    } else if let Some(exclude) = exclude {
-        println!("Filtering store keys using {}", exclude.display());
-        let file = std::fs::File::open(exclude)?;
-        let reader = std::io::BufReader::new(file);
-        let keys: Vec<String> = reader.lines().collect::<Result<_, _>>()?;
-        store.remove_many(&keys);
+    Ok(store)
+}
+
+fn validate(
+    format: Option<&Format>,
+    input: &Path,
+    fail_on: FailOn,
+    specs_args: &SpecsArgs,
+) -> Result<()> {
+    let loaded = read_store(format, input)?;
+    let name = describe_input(input);
+    println!("{} is {}", name, loaded.describe_format());
+    let LoadedStore { store, version, .. } = loaded;
+
+    if version < StoreVersion::CURRENT {
+        println!(
+            "{} uses the {} store layout; run `ria migrate` to upgrade it to {}",
+            name,
+            version,
+            StoreVersion::CURRENT
+        );
     }
 
-    // Write the store to the output file
-    println!("Writing store to {}", output.display());
-    let mut writer = std::io::BufWriter::new(file);
+    let mut report: BTreeMap<&str, Vec<Issue>> = store.validate().into_iter().collect();
+    if specs_args.check_specs {
+        let tolerance = SpecsTolerance {
+            nd: specs_args.nd_tol,
+            vd: specs_args.vd_tol,
+        };
+        for (key, issues) in store.check_specs(&tolerance) {
+            report.entry(key).or_default().extend(issues);
+        }
+    }
+    let mut stdout = std::io::stdout().lock();
+    for (key, issues) in &report {
+        writeln!(stdout, "{}", key)?;
+        for issue in issues {
+            writeln!(stdout, "  {}", issue)?;
+        }
+    }
+
+    let count = |severity| {
+        report
+            .values()
+            .flatten()
+            .filter(|issue| issue.severity == severity)
+            .count()
+    };
+    let (errors, warnings) = (count(Severity::Error), count(Severity::Warning));
+    writeln!(
+        stdout,
+        "Checked {} materials: {} errors and {} warnings",
+        store.keys().count(),
+        errors,
+        warnings
+    )?;
 
-    match format {
-        Format::Json => serde_json::to_writer(writer, &store)?,
-        Format::Bitcode => {
-            let data = bitcode::serialize(&store)?;
-            writer.write_all(&data)?;
+    let failures = match fail_on {
+        FailOn::Errors => errors,
+        FailOn::Warnings => errors + warnings,
+    };
+    if failures > 0 {
+        return Err(anyhow::anyhow!(
+            "{} failed validation with {} errors and {} warnings",
+            name,
+            errors,
+            warnings
+        ));
+    }
+    Ok(())
+}
+
+fn migrate(
+    format: Option<&Format>,
+    input: &Path,
+    output: &Path,
+    output_args: &OutputArgs,
+) -> Result<()> {
+    let loaded = read_store(format, input)?;
+    info!(
+        "Migrating store from the {} layout to {}",
+        loaded.version,
+        StoreVersion::CURRENT
+    );
+    if loaded.version < StoreVersion::V3 {
+        warn!(
+            "The {} layout does not record the catalog, so every material is assumed to be from the nk catalog. Rebuild stores of the n2 catalog with ria store -c n2",
+            loaded.version
+        );
+    }
+
+    // The upgraded store keeps the format of the input unless one is given
+    info!("Writing store to {}", describe(output));
+    save(
+        format.unwrap_or(&loaded.format),
+        output_args,
+        &loaded.store,
+        output,
+    )
+}
+
+fn convert(
+    from: Option<&Format>,
+    to: &Format,
+    input: &Path,
+    output: &Path,
+    select_args: &SelectArgs,
+    output_args: &OutputArgs,
+) -> Result<()> {
+    let (mut store, _) = load(from, input)?;
+    select_args.select(&mut store)?;
+
+    info!("Writing store to {}", describe(output));
+    save(to, output_args, &store, output)
+}
+
+fn get(format: Option<&Format>, key: &str, input: &Path, json: bool) -> Result<()> {
+    // The material and the n2 materials of its book in a combined store
+    let n2_prefix = n2_key_prefix(key);
+    let store = load_matching(format, input, |other| {
+        other == key
+            || n2_prefix
+                .as_ref()
+                .is_some_and(|prefix| other.starts_with(prefix))
+    })?;
+    let material = store
+        .get(key)
+        .ok_or(anyhow::anyhow!("Key not found in store: {}", key))?;
+
+    let summary = MaterialSummary {
+        key,
+        shelf: &material.shelf,
+        book: &material.book,
+        page: &material.page,
+        catalog: material.catalog,
+        shelf_divider: material.shelf_divider.as_deref(),
+        book_divider: material.book_divider.as_deref(),
+        references: &material.references,
+        comments: &material.comments,
+        data: material
+            .data
+            .iter()
+            .map(|data| {
+                let (method, polarization) = match data {
+                    DispersionData::TabulatedN2 {
+                        method,
+                        polarization,
+                        ..
+                    } => (method.clone(), polarization.clone()),
+                    _ => (None, None),
+                };
+                DataSummary {
+                    data_type: data.type_name(),
+                    wavelength_range: data.wavelength_range(),
+                    points: data.is_tabulated().then(|| data.len()),
+                    coefficients: data.is_formula().then(|| data.len()),
+                    method,
+                    polarization,
+                }
+            })
+            .collect(),
+        n2: store
+            .n2_of(key)
+            .into_iter()
+            .map(|(n2_key, _)| n2_key.as_str())
+            .filter(|n2_key| *n2_key != key)
+            .collect(),
+    };
+
+    let mut stdout = std::io::stdout().lock();
+    if json {
+        serde_json::to_writer_pretty(&mut stdout, &summary)?;
+        writeln!(stdout)?;
+        return Ok(());
+    }
+
+    let optional = |value: Option<&str>| value.unwrap_or("-").to_string();
+    writeln!(stdout, "Key:           {}", summary.key)?;
+    writeln!(stdout, "Shelf:         {}", summary.shelf)?;
+    writeln!(stdout, "Book:          {}", summary.book)?;
+    writeln!(stdout, "Page:          {}", summary.page)?;
+    writeln!(stdout, "Catalog:       {}", summary.catalog)?;
+    writeln!(stdout, "Shelf divider: {}", optional(summary.shelf_divider))?;
+    writeln!(stdout, "Book divider:  {}", optional(summary.book_divider))?;
+    writeln!(stdout, "References:    {}", summary.references)?;
+    writeln!(stdout, "Comments:      {}", summary.comments)?;
+    writeln!(stdout, "Data:")?;
+    for data in &summary.data {
+        let range = match data.wavelength_range {
+            Some([min, max]) => format!("{}-{} µm", min, max),
+            None => "no wavelengths".to_string(),
+        };
+        let count = match (data.points, data.coefficients) {
+            (Some(points), _) => format!("{} points", points),
+            (_, Some(coefficients)) => format!("{} coefficients", coefficients),
+            _ => "not supported".to_string(),
+        };
+        let conditions: Vec<String> = [
+            data.method.clone(),
+            data.polarization
+                .as_ref()
+                .map(|p| format!("{} polarization", p)),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        let conditions = match conditions.is_empty() {
+            true => String::new(),
+            false => format!(" ({})", conditions.join(", ")),
+        };
+        writeln!(
+            stdout,
+            "  {}: {}, {}{}",
+            data.data_type, range, count, conditions
+        )?;
+    }
+    if !summary.n2.is_empty() {
+        writeln!(stdout, "n2 data:")?;
+        for key in &summary.n2 {
+            writeln!(stdout, "  {}", key)?;
         }
     }
 
     Ok(())
 }
 
-fn validate(format: &Format, input: &PathBuf) -> Result<()> {
-    // Open the file specified in the args
-    let file = std::fs::File::open(input)?;
-    let reader = std::io::BufReader::new(file);
+fn list(
+    format: Option<&Format>,
+    input: &Path,
+    filter_args: &FilterArgs,
+    keys_only: bool,
+) -> Result<()> {
+    let (store, _) = load(format, input)?;
+    let filter = filter_args.filter()?;
+
+    let mut matches: Vec<_> = store
+        .iter()
+        .filter(|(key, material)| filter.matches(key, material))
+        .map(|(key, material)| (key.as_str(), material))
+        .collect();
+    matches.sort_by_key(|(key, _)| *key);
+
+    print_keys(&matches, keys_only)
+}
+
+fn search(
+    format: Option<&Format>,
+    text: &str,
+    input: &Path,
+    fuzzy: bool,
+    limit: Option<usize>,
+    keys_only: bool,
+) -> Result<()> {
+    let (store, _) = load(format, input)?;
+
+    let mut matches: Vec<(&str, &Material)> = if fuzzy {
+        store
+            .search(text)
+            .into_iter()
+            .filter_map(|(_, key)| Some((key, store.get(key)?)))
+            .collect()
+    } else {
+        let text = text.to_lowercase();
+        let mut matches: Vec<_> = store
+            .iter()
+            .filter(|(key, material)| {
+                [
+                    key.as_str(),
+                    &material.book,
+                    &material.page,
+                    &material.comments,
+                    &material.references,
+                ]
+                .iter()
+                .any(|field| field.to_lowercase().contains(&text))
+            })
+            .map(|(key, material)| (key.as_str(), material))
+            .collect();
+        matches.sort_by_key(|(key, _)| *key);
+        matches
+    };
+    if let Some(limit) = limit {
+        matches.truncate(limit);
+    }
+
+    print_keys(&matches, keys_only)
+}
+
+fn find(
+    format: Option<&Format>,
+    input: &Path,
+    targets: &TargetArgs,
+    limit: Option<usize>,
+    keys_only: bool,
+) -> Result<()> {
+    let constraints = targets.constraints();
+    if constraints.is_empty() {
+        return Err(anyhow::anyhow!(
+            "Provide at least one of --n, --k, --vd and --dn-dlambda"
+        ));
+    }
+
+    let (store, _) = load(format, input)?;
+    let mut matches: Vec<(&str, &Material)> = store
+        .find(&constraints)
+        .into_iter()
+        .filter_map(|(_, key)| Some((key, store.get(key)?)))
+        .collect();
+    if let Some(limit) = limit {
+        matches.truncate(limit);
+    }
+
+    print_keys(&matches, keys_only)
+}
+
+fn substitutes(
+    format: Option<&Format>,
+    key: &str,
+    input: &Path,
+    count: usize,
+    keys_only: bool,
+) -> Result<()> {
+    let (store, _) = load(format, input)?;
+    let matches = store.nearest_glasses(key, count)?;
+
+    let mut stdout = std::io::stdout().lock();
+    if !keys_only {
+        writeln!(stdout, "key\tnd\tvd\tpgf\trms\tdistance")?;
+    }
+    for glass in matches {
+        if keys_only {
+            writeln!(stdout, "{}", glass.key)?;
+            continue;
+        }
+        let pgf = glass
+            .pgf
+            .map(|pgf| format!("{:.4}", pgf))
+            .unwrap_or_default();
+        writeln!(
+            stdout,
+            "{}\t{:.5}\t{:.2}\t{}\t{:.5}\t{:.3}",
+            glass.key, glass.nd, glass.vd, pgf, glass.rms, glass.distance
+        )?;
+    }
+    Ok(())
+}
+
+fn glass_map(
+    format: Option<&Format>,
+    input: &Path,
+    output: Option<&PathBuf>,
+    output_format: ChartFormat,
+    shelf: Option<&str>,
+    highlight: &[String],
+) -> Result<()> {
+    let (mut store, _) = load(format, input)?;
+    if let Some(shelf) = shelf {
+        let filter = Filter::Shelf(shelf.to_string());
+        store.retain(|key, material| filter.matches(key, material));
+    }
 
-    // Deserialize the store file
-    let _store: Store = match format {
-        Format::Json => serde_json::from_reader(reader)?,
-        Format::Bitcode => {
-            let data = reader.bytes().collect::<Result<Vec<u8>, _>>()?;
-            bitcode::deserialize(&data)?
+    let map = store.glass_map();
+    let contents = match output_format {
+        ChartFormat::Csv => map.to_csv(),
+        ChartFormat::Svg => {
+            let highlight: Vec<&str> = highlight.iter().map(String::as_str).collect();
+            map.to_svg(&highlight)
         }
     };
 
+    match output {
+        Some(output) => {
+            info!(
+                "Writing a glass map of {} materials to {}",
+                map.points.len(),
+                output.display()
+            );
+            std::fs::write(output, contents)?;
+        }
+        None => std::io::stdout().lock().write_all(contents.as_bytes())?,
+    }
     Ok(())
 }
 
+fn plot(
+    format: Option<&Format>,
+    keys: &[String],
+    input: &Path,
+    output: &Path,
+    log_k: bool,
+) -> Result<()> {
+    let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+    let store = load_matching(format, input, |key| keys.contains(&key))?;
+    let svg = store.plot(&keys, &PlotOptions { log_k })?;
+
+    info!("Writing plot to {}", describe(output));
+    create_output(output)?.write_all(svg.as_bytes())?;
+    Ok(())
+}
+
+/// Prints the keys and names of materials, one per line.
+fn print_keys(materials: &[(&str, &Material)], keys_only: bool) -> Result<()> {
+    let mut stdout = std::io::stdout().lock();
+    for (key, material) in materials {
+        if keys_only {
+            writeln!(stdout, "{}", key)?;
+        } else {
+            writeln!(stdout, "{}\t{}", key, material.name())?;
+        }
+    }
+    Ok(())
+}
+
+fn eval(
+    format: Option<&Format>,
+    key: &str,
+    input: &Path,
+    grid: &GridArgs,
+    units: Units,
+    output_format: TableFormat,
+    options: &EvalOptions,
+) -> Result<()> {
+    let store = load_matching(format, input, |other| other == key)?;
+    let material = store
+        .get(key)
+        .ok_or(anyhow::anyhow!("Key not found in store: {}", key))?;
+
+    let rows: Vec<EvalRow> = grid
+        .wavelengths()?
+        .into_iter()
+        .map(|wavelength| {
+            let micrometers = units.to_micrometers(wavelength);
+            let n = material.n_with(micrometers, options);
+            let k = material.k_with(micrometers, options);
+            let error = match (&n, &k) {
+                (Err(err), _) | (_, Err(err)) => Some(err.to_string()),
+                _ => None,
+            };
+            EvalRow {
+                wavelength,
+                n: n.ok(),
+                k: k.ok().flatten(),
+                error,
+            }
+        })
+        .collect();
+
+    let mut stdout = std::io::stdout().lock();
+    match output_format {
+        TableFormat::Json => {
+            serde_json::to_writer_pretty(&mut stdout, &rows)?;
+            writeln!(stdout)?;
+        }
+        TableFormat::Csv | TableFormat::Tsv => {
+            let sep = if output_format == TableFormat::Csv {
+                ","
+            } else {
+                "\t"
+            };
+            let optional = |value: Option<f64>| value.map(|v| v.to_string()).unwrap_or_default();
+            writeln!(stdout, "wavelength_{}{sep}n{sep}k{sep}error", units)?;
+            for row in &rows {
+                // Errors are quoted so that commas in messages do not break CSV
+                let error = row
+                    .error
+                    .as_ref()
+                    .map(|e| format!("\"{}\"", e.replace('"', "\"\"")))
+                    .unwrap_or_default();
+                writeln!(
+                    stdout,
+                    "{}{sep}{}{sep}{}{sep}{}",
+                    row.wavelength,
+                    optional(row.n),
+                    optional(row.k),
+                    error
+                )?;
+            }
+        }
+    }
+
+    let failed = rows.iter().filter(|row| row.error.is_some()).count();
+    if failed > 0 {
+        warn!(
+            "{} of {} wavelengths could not be evaluated",
+            failed,
+            rows.len()
+        );
+    }
+
+    Ok(())
+}
+
+/// Writes a store to a file, or to stdout if the path is `-`, in the given
+/// format, optionally compressing it.
+fn save(format: &Format, output_args: &OutputArgs, store: &Store, output: &Path) -> Result<()> {
+    output_args.check(format)?;
+    let mut data = match format {
+        Format::Json => {
+            let mut data = Vec::new();
+            store.to_json_writer(&mut data)?;
+            data
+        }
+        Format::Bitcode => store.to_bitcode()?,
+        Format::Mmap => store.to_indexed()?,
+        Format::Compact => {
+            let precision = output_args.precision.unwrap_or(PrecisionChoice::F64);
+            store.to_compact(precision.into())?
+        }
+    };
+
+    if let Some(method) = &output_args.compress {
+        data = compress(&data, method.into(), output_args.level)?;
+    }
+
+    let mut writer = create_output(output)?;
+    writer.write_all(&data)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// The number of bytes that identify an indexed store.
+const INDEXED_MAGIC_LEN: u64 = 6;
+
+/// The largest number of wavelengths that `eval` evaluates.
+const MAX_GRID_POINTS: usize = 1_000_000;
+
+/// The path of the database when none is given.
+const DEFAULT_DATABASE_PATH: &str = "./database";
+
+/// The path that stands for standard input or output.
+const STDIO: &str = "-";
+
+/// Returns `true` if the path stands for standard input or output.
+fn is_stdio(path: &Path) -> bool {
+    path.as_os_str() == STDIO
+}
+
+/// Reads a whole file, or stdin if the path is `-`.
+fn read_input(input: &Path) -> Result<Vec<u8>> {
+    if is_stdio(input) {
+        let mut data = Vec::new();
+        std::io::stdin().lock().read_to_end(&mut data)?;
+        return Ok(data);
+    }
+    Ok(std::fs::read(input)?)
+}
+
+/// Creates a file for writing, or returns stdout if the path is `-`.
+fn create_output(output: &Path) -> Result<Box<dyn Write>> {
+    if is_stdio(output) {
+        return Ok(Box::new(std::io::stdout().lock()));
+    }
+    Ok(Box::new(std::io::BufWriter::new(std::fs::File::create(
+        output,
+    )?)))
+}
+
+/// Describes an output path in messages.
+fn describe(output: &Path) -> String {
+    match is_stdio(output) {
+        true => "stdout".to_string(),
+        false => output.display().to_string(),
+    }
+}
+
+/// Describes an input path in messages.
+fn describe_input(input: &Path) -> String {
+    match is_stdio(input) {
+        true => "stdin".to_string(),
+        false => input.display().to_string(),
+    }
+}
+
+/// A store read from a file and the way it was stored.
+struct LoadedStore {
+    store: Store,
+    version: StoreVersion,
+    format: Format,
+    compression: Option<Compression>,
+
+    /// `true` if the format was detected rather than given.
+    detected: bool,
+}
+
+impl LoadedStore {
+    /// Describes how the store was stored, e.g. "a json store, compressed
+    /// with zstd (detected)".
+    fn describe_format(&self) -> String {
+        let compression = self
+            .compression
+            .map(|compression| format!(", compressed with {}", compression))
+            .unwrap_or_default();
+        format!(
+            "a {} store{}{}",
+            self.format,
+            compression,
+            if self.detected { " (detected)" } else { "" }
+        )
+    }
+}
+
+/// Reads a store written by any supported version of ria, decompressing it
+/// if necessary. Unless a format is given, it is detected from the file's
+/// contents and extension.
+fn read_store(format: Option<&Format>, input: &Path) -> Result<LoadedStore> {
+    let data = read_input(input)?;
+    let compression = Compression::detect(&data);
+    let data = decompress(&data)?;
+
+    let detected = format.is_none();
+    // Data in no recognized format are read as bitcode, which has no magic
+    // number
+    let recognized = match format {
+        Some(format) => Some(format.clone()),
+        None => StoreFormat::detect_with_extension(&data, input).map(Format::from),
+    };
+    let format = match &recognized {
+        Some(format) => format.clone(),
+        None => StoreFormat::assume_bitcode(&data)?.into(),
+    };
+    let (store, version) = match format {
+        Format::Json => Store::migrate_from_json(&data),
+        Format::Bitcode => Store::migrate_from_bitcode(&data),
+        Format::Mmap => Ok((
+            IndexedStore::from_bytes(data)?.to_store()?,
+            StoreVersion::CURRENT,
+        )),
+        Format::Compact => Ok((Store::from_compact_bytes(&data)?, StoreVersion::CURRENT)),
+    }
+    .map_err(|e| match (detected, recognized.is_some()) {
+        (true, false) => anyhow::anyhow!(
+            "Unrecognized store format: {} is not a JSON, indexed or compact store and cannot be read as bitcode; use --format to give its format: {}",
+            input.display(),
+            e
+        ),
+        (true, true) => anyhow::anyhow!(
+            "{} was detected as a {} store but cannot be read as one; use --format to override: {}",
+            input.display(),
+            format,
+            e
+        ),
+        (false, _) => e,
+    })?;
+
+    let loaded = LoadedStore {
+        store,
+        version,
+        format,
+        compression,
+        detected,
+    };
+    info!(
+        "Read {} as {}",
+        describe_input(input),
+        loaded.describe_format()
+    );
+    Ok(loaded)
+}
+
+/// Reads a store; see [`read_store`].
+fn load(format: Option<&Format>, input: &Path) -> Result<(Store, StoreVersion)> {
+    let loaded = read_store(format, input)?;
+    Ok((loaded.store, loaded.version))
+}
+
+/// Reads the materials of a store whose keys satisfy a predicate, for the
+/// commands that look materials up by key. Uncompressed indexed store files
+/// are memory-mapped and only the selected materials are decoded, while other
+/// stores are read in full.
+fn load_matching(
+    format: Option<&Format>,
+    input: &Path,
+    predicate: impl Fn(&str) -> bool,
+) -> Result<Store> {
+    match open_indexed(format, input)? {
+        Some(indexed) => indexed.select(predicate),
+        None => Ok(load(format, input)?.0),
+    }
+}
+
+/// Memory-maps a store file if it is an uncompressed indexed store and no
+/// other format is given.
+///
+/// # Returns
+/// The indexed store, or `None` if the input is not one.
+fn open_indexed(format: Option<&Format>, input: &Path) -> Result<Option<IndexedStore<Mmap>>> {
+    if is_stdio(input) || !matches!(format, None | Some(Format::Mmap)) {
+        return Ok(None);
+    }
+    let mut magic = Vec::new();
+    std::fs::File::open(input)?
+        .take(INDEXED_MAGIC_LEN)
+        .read_to_end(&mut magic)?;
+    if StoreFormat::detect(&magic) != Some(StoreFormat::Indexed) {
+        return Ok(None);
+    }
+
+    let indexed = IndexedStore::open(input)?;
+    info!(
+        "Opened {} as a memory-mapped {} store of {} materials",
+        input.display(),
+        Format::Mmap,
+        indexed.len()
+    );
+    Ok(Some(indexed))
+}
+
 #[derive(Parser, Debug)]
 #[clap(version, author)]
 pub struct Args {
-    /// The format of the file containing the serialized store
-    #[arg(short, long, value_name = "FORMAT", default_value = "json")]
-    pub format: Format,
+    /// The format of the store file. Stores that are read are detected from
+    /// their contents and extension unless this is given, and stores that are
+    /// written default to json.
+    #[arg(short, long, value_name = "FORMAT")]
+    pub format: Option<Format>,
+
+    /// Print more messages to standard error; repeat for more detail
+    #[arg(short, long, action = ArgAction::Count, global = true)]
+    pub verbose: u8,
+
+    /// Print fewer messages to standard error; repeat to print only errors
+    #[arg(short, long, action = ArgAction::Count, global = true)]
+    pub quiet: u8,
+
+    /// The format of the messages printed to standard error
+    #[arg(long, value_name = "FORMAT", default_value = "text", global = true)]
+    pub log_format: LogFormat,
 
     #[command(subcommand)]
     pub command: Commands,
 }
 
-#[derive(ValueEnum, Debug, Clone)]
+impl Args {
+    /// The most detailed level of the messages to print. Progress messages
+    /// are printed by default.
+    fn log_level(&self) -> LevelFilter {
+        match 2 + i16::from(self.verbose) - i16::from(self.quiet) {
+            ..=0 => LevelFilter::Error,
+            1 => LevelFilter::Warn,
+            2 => LevelFilter::Info,
+            3 => LevelFilter::Debug,
+            _ => LevelFilter::Trace,
+        }
+    }
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+pub enum LogFormat {
+    /// Plain messages, with the level for anything but progress
+    Text,
+
+    /// One JSON object per line with the level, target and message
+    Json,
+}
+
+/// Prints log messages from the CLI and the library to standard error, so that
+/// they never mix with the data that are written to standard output.
+struct Logger {
+    level: LevelFilter,
+    format: LogFormat,
+}
+
+impl Logger {
+    /// Installs the logger for the rest of the program.
+    fn init(level: LevelFilter, format: LogFormat) -> Result<()> {
+        log::set_boxed_logger(Box::new(Logger { level, format }))?;
+        log::set_max_level(level);
+        Ok(())
+    }
+
+    /// Formats a message as a single line.
+    fn format(&self, record: &Record) -> String {
+        match self.format {
+            LogFormat::Text => match record.level() {
+                Level::Info => record.args().to_string(),
+                Level::Warn => format!("warning: {}", record.args()),
+                level => format!("{}: {}", level.as_str().to_lowercase(), record.args()),
+            },
+            LogFormat::Json => serde_json::json!({
+                "level": record.level().as_str().to_lowercase(),
+                "target": record.target(),
+                "message": record.args().to_string(),
+            })
+            .to_string(),
+        }
+    }
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            // Messages that cannot be written are dropped rather than failing
+            // the command
+            let _ = writeln!(std::io::stderr().lock(), "{}", self.format(record));
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+#[derive(ValueEnum, Deserialize, Debug, Clone)]
+#[serde(rename_all = "lowercase")]
 pub enum Format {
     Json,
     Bitcode,
+
+    /// An indexed binary store that can be memory-mapped and read one
+    /// material at a time
+    Mmap,
+
+    /// A small binary store with shared strings and columnar tables
+    Compact,
+}
+
+impl From<StoreFormat> for Format {
+    fn from(format: StoreFormat) -> Self {
+        match format {
+            StoreFormat::Json => Format::Json,
+            StoreFormat::Bitcode => Format::Bitcode,
+            StoreFormat::Indexed => Format::Mmap,
+            StoreFormat::Compact => Format::Compact,
+        }
+    }
+}
+
+impl std::fmt::Display for Format {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Format::Json => write!(f, "json"),
+            Format::Bitcode => write!(f, "bitcode"),
+            Format::Mmap => write!(f, "mmap"),
+            Format::Compact => write!(f, "compact"),
+        }
+    }
 }
 
 #[derive(Subcommand, Debug)]
 pub enum Commands {
     /// Creates a single file store of the refractiveindex.info database
     Store {
-        /// The path to the refractiveindex.info database folders
-        #[arg(short, long, value_name = "PATH", default_value = "./database")]
-        path: std::path::PathBuf,
+        /// The path to the refractiveindex.info database folders [default:
+        /// ./database]
+        #[arg(short, long, value_name = "PATH")]
+        path: Option<std::path::PathBuf>,
 
         /// The catalog to parse
         #[arg(short, long, value_name = "TYPE", default_value = "nk")]
         catalog: CatalogChoice,
 
-        /// The file to write the parsed results to
+        /// The file to write the parsed results to, or - for standard output
         #[arg(short, long, value_name = "FILE", default_value = "./results.dat")]
         output: std::path::PathBuf,
 
-        /// A file containing store keys to inculde in the output file. There
-        /// should be one key per line. If this is not provided, all keys will
-        /// be included.
+        /// A TOML file describing one or more stores to write, each with its
+        /// own catalog, key and property filters, format and compression.
+        /// The database path is taken from the file if it gives one and
+        /// --path is not given.
+        #[arg(
+            long,
+            value_name = "FILE",
+            conflicts_with_all = [
+                "catalog", "output", "include", "exclude", "shelf", "book", "divider",
+                "data_type", "has_k", "covers", "compress", "level", "precision",
+            ]
+        )]
+        config: Option<std::path::PathBuf>,
+
+        #[command(flatten)]
+        select_args: SelectArgs,
+
+        #[command(flatten)]
+        output_args: OutputArgs,
+    },
+
+    /// Validates a store and checks the data of every material, printing
+    /// the issues found for each key
+    Validate {
+        /// The store file to read, or - for standard input
+        #[arg(short, long, value_name = "FILE", default_value = "./results.dat")]
+        input: std::path::PathBuf,
+
+        /// Exits with an error if any issue is at least this severe
+        #[arg(long, value_name = "SEVERITY", default_value = "errors")]
+        fail_on: FailOn,
+
+        #[command(flatten)]
+        specs_args: SpecsArgs,
+    },
+
+    /// Rewrites a store created by an older version of ria in the current
+    /// layout
+    ///
+    /// Stores written before materials were tagged with their catalog are
+    /// assumed to hold the nk catalog. Stores of the n2 catalog must be
+    /// rebuilt with ria store -c n2 instead.
+    Migrate {
+        /// The store file to upgrade, or - for standard input
+        #[arg(short, long, value_name = "FILE", default_value = "./results.dat")]
+        input: std::path::PathBuf,
+
+        /// The file to write the upgraded store to, or - for standard output
         #[arg(short, long, value_name = "FILE")]
-        include: Option<std::path::PathBuf>,
+        output: std::path::PathBuf,
+
+        #[command(flatten)]
+        output_args: OutputArgs,
+    },
+
+    /// Converts a store from one format to another
+    Convert {
+        /// The format of the input store; detected from its contents and
+        /// extension if omitted
+        #[arg(long, value_name = "FORMAT")]
+        from: Option<Format>,
+
+        /// The format of the output store
+        #[arg(long, value_name = "FORMAT")]
+        to: Format,
+
+        /// The store file to read, or - for standard input
+        #[arg(value_name = "INPUT")]
+        input: std::path::PathBuf,
+
+        /// The store file to write, or - for standard output
+        #[arg(value_name = "OUTPUT")]
+        output: std::path::PathBuf,
+
+        #[command(flatten)]
+        select_args: SelectArgs,
+
+        #[command(flatten)]
+        output_args: OutputArgs,
+    },
+
+    /// Prints a summary of a single material in a store
+    Get {
+        /// The key of the material, e.g. glass:BK7:SCHOTT
+        key: String,
+
+        /// The store file to read, or - for standard input
+        #[arg(short, long, value_name = "FILE", default_value = "./results.dat")]
+        input: std::path::PathBuf,
+
+        /// Prints the summary as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Lists the materials in a store, optionally filtered by shelf, book,
+    /// divider, data type or wavelength coverage
+    List {
+        /// The store file to read, or - for standard input
+        #[arg(short, long, value_name = "FILE", default_value = "./results.dat")]
+        input: std::path::PathBuf,
+
+        #[command(flatten)]
+        filter_args: FilterArgs,
+
+        /// Prints only the keys, e.g. to create an include file
+        #[arg(long)]
+        keys_only: bool,
+    },
+
+    /// Searches the keys, names, comments and references of the materials in
+    /// a store
+    Search {
+        /// The text to search for, ignoring case
+        text: String,
+
+        /// The store file to read, or - for standard input
+        #[arg(short, long, value_name = "FILE", default_value = "./results.dat")]
+        input: std::path::PathBuf,
+
+        /// Ranks materials by how well their keys, names and dividers match
+        /// the words of the text, e.g. "schott bk 7" or "silver johnson"
+        #[arg(long)]
+        fuzzy: bool,
+
+        /// Prints at most this many materials
+        #[arg(short = 'n', long, value_name = "COUNT")]
+        limit: Option<usize>,
+
+        /// Prints only the keys, e.g. to create an include file
+        #[arg(long)]
+        keys_only: bool,
+    },
+
+    /// Finds materials by their refractive index, extinction coefficient,
+    /// Abbe number or dispersion, closest matches first
+    Find {
+        /// The store file to read, or - for standard input
+        #[arg(short, long, value_name = "FILE", default_value = "./results.dat")]
+        input: std::path::PathBuf,
+
+        #[command(flatten)]
+        targets: TargetArgs,
+
+        /// Prints at most this many materials
+        #[arg(long, value_name = "COUNT")]
+        limit: Option<usize>,
+
+        /// Prints only the keys, e.g. to create an include file
+        #[arg(long)]
+        keys_only: bool,
+    },
+
+    /// Finds the glasses on the same shelf that are the closest substitutes
+    /// for a glass by nd, Vd, PgF and dispersion curve
+    Substitutes {
+        /// The key of the glass to replace, e.g. glass:BK7:SCHOTT
+        key: String,
+
+        /// The store file to read, or - for standard input
+        #[arg(short, long, value_name = "FILE", default_value = "./results.dat")]
+        input: std::path::PathBuf,
+
+        /// The number of glasses to print
+        #[arg(short = 'n', long, value_name = "COUNT", default_value_t = 10)]
+        count: usize,
+
+        /// Prints only the keys, e.g. to create an include file
+        #[arg(long)]
+        keys_only: bool,
+    },
+
+    /// Writes a glass map (Abbe diagram) of nd against Vd for every material
+    /// whose data cover the d, F and C lines
+    GlassMap {
+        /// The store file to read, or - for standard input
+        #[arg(short, long, value_name = "FILE", default_value = "./results.dat")]
+        input: std::path::PathBuf,
 
-        /// A file containing store keys to exclude from the output file. There
-        /// should be one key per line. This will be ignored if the include file
-        /// is provided.
+        /// The file to write; the map is written to stdout if omitted
         #[arg(short, long, value_name = "FILE")]
-        exclude: Option<std::path::PathBuf>,
+        output: Option<std::path::PathBuf>,
+
+        /// The format of the map
+        #[arg(long, value_name = "FORMAT", default_value = "svg")]
+        output_format: ChartFormat,
+
+        /// Includes only materials on this shelf, e.g. glass
+        #[arg(long, value_name = "SHELF")]
+        shelf: Option<String>,
+
+        /// Comma-separated keys of materials to highlight in the SVG map
+        #[arg(long, value_name = "KEYS", value_delimiter = ',')]
+        highlight: Vec<String>,
     },
 
-    /// Validates a JSON dump of the refractiveindex.info database
-    Validate {
-        /// The path to the JSON dump of the refractiveindex.info database
+    /// Plots n and k against wavelength for one or more materials as SVG
+    Plot {
+        /// The keys of the materials, e.g. glass:BK7:SCHOTT
+        #[arg(required = true)]
+        keys: Vec<String>,
+
+        /// The store file to read, or - for standard input
+        #[arg(short, long, value_name = "FILE", default_value = "./results.dat")]
+        input: std::path::PathBuf,
+
+        /// The SVG file to write
+        #[arg(short, long, value_name = "FILE", default_value = "./plot.svg")]
+        output: std::path::PathBuf,
+
+        /// Draws k on a logarithmic axis
+        #[arg(long)]
+        log_k: bool,
+    },
+
+    /// Evaluates the refractive index of a material over a range of
+    /// wavelengths
+    Eval {
+        /// The key of the material, e.g. glass:BK7:SCHOTT
+        key: String,
+
+        /// The store file to read, or - for standard input
         #[arg(short, long, value_name = "FILE", default_value = "./results.dat")]
         input: std::path::PathBuf,
+
+        #[command(flatten)]
+        grid: GridArgs,
+
+        /// The units of the wavelengths
+        #[arg(short, long, value_name = "UNITS", default_value = "um")]
+        units: Units,
+
+        /// The format of the table written to stdout
+        #[arg(long, value_name = "FORMAT", default_value = "csv")]
+        output_format: TableFormat,
+
+        /// How tabulated data are interpolated between sample points
+        #[arg(long, value_name = "METHOD", default_value = "linear")]
+        interpolation: InterpolationChoice,
+
+        /// How wavelengths outside of the range of the data are handled
+        #[arg(long, value_name = "METHOD", default_value = "error")]
+        extrapolation: ExtrapolationChoice,
     },
 }
 
-#[derive(ValueEnum, Debug, Clone)]
+#[derive(ValueEnum, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
 pub enum CatalogChoice {
     N2,
     NK,
+
+    /// Both catalogs in one store, with the keys of n2 materials prefixed
+    /// with n2:
+    All,
+}
+
+/// The output of the `get` command.
+#[derive(Serialize, Debug)]
+struct MaterialSummary<'a> {
+    key: &'a str,
+    shelf: &'a str,
+    book: &'a str,
+    page: &'a str,
+    catalog: CatalogKind,
+    shelf_divider: Option<&'a str>,
+    book_divider: Option<&'a str>,
+    references: &'a str,
+    comments: &'a str,
+    data: Vec<DataSummary<'a>>,
+
+    /// The n2 materials of the same book in a combined store.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    n2: Vec<&'a str>,
+}
+
+#[derive(Serialize, Debug)]
+struct DataSummary<'a> {
+    #[serde(rename = "type")]
+    data_type: &'a str,
+    wavelength_range: Option<[f64; 2]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    points: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    coefficients: Option<usize>,
+
+    /// How n2 data were measured and with which polarization.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    method: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    polarization: Option<String>,
+}
+
+/// One row of the output of the `eval` command.
+#[derive(Serialize, Debug)]
+struct EvalRow {
+    wavelength: f64,
+    n: Option<f64>,
+    k: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// The optical properties to search for with the `find` command.
+#[derive(clap::Args, Debug)]
+pub struct TargetArgs {
+    /// The target real part of the refractive index
+    #[arg(long, value_name = "N", allow_negative_numbers = true)]
+    pub n: Option<f64>,
+
+    /// The target imaginary part of the refractive index
+    #[arg(long, value_name = "K")]
+    pub k: Option<f64>,
+
+    /// The target Abbe number Vd
+    #[arg(long, value_name = "VD")]
+    pub vd: Option<f64>,
+
+    /// The target derivative of n with respect to wavelength in 1/µm
+    #[arg(long, value_name = "DN", allow_negative_numbers = true)]
+    pub dn_dlambda: Option<f64>,
+
+    /// Comma-separated wavelengths in µm at which n, k and dn/dλ must match
+    #[arg(
+        long,
+        value_name = "WAVELENGTHS",
+        value_delimiter = ',',
+        default_value = "0.5875618"
+    )]
+    pub at: Vec<f64>,
+
+    /// The tolerance of n
+    #[arg(long, value_name = "TOL", default_value_t = 0.01)]
+    pub tol: f64,
+
+    /// The tolerance of k
+    #[arg(long, value_name = "TOL", default_value_t = 0.01)]
+    pub k_tol: f64,
+
+    /// The tolerance of the Abbe number
+    #[arg(long, value_name = "TOL", default_value_t = 1.0)]
+    pub vd_tol: f64,
+
+    /// The tolerance of dn/dλ in 1/µm
+    #[arg(long, value_name = "TOL", default_value_t = 0.01)]
+    pub dn_tol: f64,
+}
+
+impl TargetArgs {
+    /// Returns the constraints given on the command line.
+    fn constraints(&self) -> Vec<Constraint> {
+        let mut constraints = Vec::new();
+        for &wavelength in &self.at {
+            let targets = [
+                (self.n, Property::N(wavelength), self.tol),
+                (self.k, Property::K(wavelength), self.k_tol),
+                (
+                    self.dn_dlambda,
+                    Property::DnDLambda(wavelength),
+                    self.dn_tol,
+                ),
+            ];
+            for (target, property, tolerance) in targets {
+                if let Some(target) = target {
+                    constraints.push(Constraint {
+                        property,
+                        target,
+                        tolerance,
+                    });
+                }
+            }
+        }
+        if let Some(target) = self.vd {
+            constraints.push(Constraint {
+                property: Property::AbbeNumber,
+                target,
+                tolerance: self.vd_tol,
+            });
+        }
+        constraints
+    }
+}
+
+/// The materials to write to a store.
+#[derive(clap::Args, Debug)]
+pub struct SelectArgs {
+    /// A file listing the store keys to include in the output file, one
+    /// key, glob pattern (e.g. glass:*:SCHOTT) or `regex:` expression per
+    /// line. Blank lines and lines starting with # are ignored. If this is
+    /// not provided, all keys will be included.
+    #[arg(short, long, value_name = "FILE")]
+    pub include: Option<std::path::PathBuf>,
+
+    /// A file listing the store keys to exclude from the output file in the
+    /// same format as the include file. Excluded keys are removed even if
+    /// they are also included.
+    #[arg(short, long, value_name = "FILE")]
+    pub exclude: Option<std::path::PathBuf>,
+
+    #[command(flatten)]
+    pub filter_args: FilterArgs,
+}
+
+impl SelectArgs {
+    /// Removes the materials that are not selected from the store. Keys
+    /// matched by the exclude file are removed even if they are also
+    /// included.
+    fn select(&self, store: &mut Store) -> Result<()> {
+        let include = self
+            .include
+            .as_ref()
+            .map(|include| {
+                info!("Including store keys listed in {}", include.display());
+                KeyList::from_file(include)
+            })
+            .transpose()?;
+        let exclude = self
+            .exclude
+            .as_ref()
+            .map(|exclude| {
+                info!("Excluding store keys listed in {}", exclude.display());
+                KeyList::from_file(exclude)
+            })
+            .transpose()?;
+
+        if let Some(include) = &include {
+            for entry in include.unmatched(store) {
+                warn!("The include entry {} matches no key", entry);
+            }
+        }
+        store.retain_keys(include.as_ref(), exclude.as_ref());
+
+        let filter = self.filter_args.filter()?;
+        store.retain(|key, material| filter.matches(key, material));
+        Ok(())
+    }
+}
+
+/// The properties that selected materials must have. Every given filter must
+/// match.
+#[derive(clap::Args, Debug)]
+pub struct FilterArgs {
+    /// Selects only materials on this shelf, e.g. glass
+    #[arg(long, value_name = "SHELF")]
+    pub shelf: Option<String>,
+
+    /// Selects only materials in this book, e.g. BK7
+    #[arg(long, value_name = "BOOK")]
+    pub book: Option<String>,
+
+    /// Selects only materials under a divider containing this text, e.g.
+    /// "Thin film"
+    #[arg(long, value_name = "TEXT")]
+    pub divider: Option<String>,
+
+    /// Selects only materials with data of this type
+    #[arg(long, value_name = "TYPE")]
+    pub data_type: Option<DataTypeChoice>,
+
+    /// Selects only materials with data for the extinction coefficient k
+    #[arg(long)]
+    pub has_k: bool,
+
+    /// Selects only materials whose n data cover this wavelength range in µm,
+    /// e.g. 0.4,0.7
+    #[arg(
+        long,
+        value_name = "MIN,MAX",
+        value_delimiter = ',',
+        allow_negative_numbers = true
+    )]
+    pub covers: Vec<f64>,
+}
+
+impl FilterArgs {
+    /// Returns a filter that matches every filter given on the command line.
+    fn filter(&self) -> Result<Filter> {
+        let mut filters = Vec::new();
+        if let Some(shelf) = &self.shelf {
+            filters.push(Filter::Shelf(shelf.clone()));
+        }
+        if let Some(book) = &self.book {
+            filters.push(Filter::Book(book.clone()));
+        }
+        if let Some(divider) = &self.divider {
+            filters.push(Filter::Divider(divider.clone()));
+        }
+        match self.data_type {
+            Some(DataTypeChoice::Formula) => filters.push(Filter::Formula),
+            Some(DataTypeChoice::Tabulated) => filters.push(Filter::Tabulated),
+            None => {}
+        }
+        if self.has_k {
+            filters.push(Filter::HasK);
+        }
+        match self.covers[..] {
+            [] => {}
+            [min, max] if min <= max => filters.push(Filter::Covers([min, max])),
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "--covers takes a minimum and a maximum wavelength, e.g. 0.4,0.7"
+                ))
+            }
+        }
+        Ok(Filter::All(filters))
+    }
+}
+
+/// Options for comparing materials with the values in their SPECS.
+#[derive(clap::Args, Debug)]
+pub struct SpecsArgs {
+    /// Compares nd and Vd computed from each material's data with the values
+    /// published in its SPECS
+    #[arg(long)]
+    pub check_specs: bool,
+
+    /// The largest accepted difference of nd from the SPECS
+    #[arg(long, value_name = "TOL", default_value_t = SpecsTolerance::default().nd, requires = "check_specs")]
+    pub nd_tol: f64,
+
+    /// The largest accepted difference of Vd from the SPECS
+    #[arg(long, value_name = "TOL", default_value_t = SpecsTolerance::default().vd, requires = "check_specs")]
+    pub vd_tol: f64,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+pub enum FailOn {
+    /// Fail if there are warnings or errors
+    Warnings,
+
+    /// Fail only if there are errors
+    Errors,
+}
+
+#[derive(ValueEnum, Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum DataTypeChoice {
+    /// Data given by a dispersion formula
+    Formula,
+
+    /// Tabulated data
+    Tabulated,
+}
+
+/// The wavelengths at which to evaluate a material.
+#[derive(clap::Args, Debug)]
+pub struct GridArgs {
+    /// The first wavelength of an evenly spaced grid
+    #[arg(long, value_name = "WAVELENGTH", requires_all = ["to", "step"])]
+    pub from: Option<f64>,
+
+    /// The last wavelength of an evenly spaced grid
+    #[arg(long, value_name = "WAVELENGTH", requires_all = ["from", "step"])]
+    pub to: Option<f64>,
+
+    /// The spacing of an evenly spaced grid
+    #[arg(long, value_name = "STEP", requires_all = ["from", "to"])]
+    pub step: Option<f64>,
+
+    /// A comma-separated list of wavelengths
+    #[arg(
+        long,
+        value_name = "WAVELENGTHS",
+        value_delimiter = ',',
+        conflicts_with_all = ["from", "to", "step"]
+    )]
+    pub at: Vec<f64>,
+}
+
+impl GridArgs {
+    /// Returns the wavelengths of the grid.
+    fn wavelengths(&self) -> Result<Vec<f64>> {
+        if !self.at.is_empty() {
+            return Ok(self.at.clone());
+        }
+
+        let (Some(from), Some(to), Some(step)) = (self.from, self.to, self.step) else {
+            return Err(anyhow::anyhow!(
+                "Provide either --at or all of --from, --to and --step"
+            ));
+        };
+        if step <= 0.0 || to < from {
+            return Err(anyhow::anyhow!(
+                "The step must be positive and --to must not be less than --from"
+            ));
+        }
+
+        // A small tolerance includes the endpoint despite rounding errors
+        let count = ((to - from) / step + 1e-9).floor() + 1.0;
+        if count.is_nan() || count > MAX_GRID_POINTS as f64 {
+            return Err(anyhow::anyhow!(
+                "The grid has {:.0} wavelengths, more than the limit of {}. Use a larger --step",
+                count,
+                MAX_GRID_POINTS
+            ));
+        }
+        let count = count as usize;
+        Ok((0..count).map(|i| from + i as f64 * step).collect())
+    }
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+pub enum Units {
+    /// Micrometers, the units of the refractiveindex.info database
+    Um,
+
+    /// Nanometers
+    Nm,
+}
+
+impl Units {
+    fn to_micrometers(self, wavelength: f64) -> f64 {
+        match self {
+            Units::Um => wavelength,
+            Units::Nm => wavelength / 1000.0,
+        }
+    }
+}
+
+impl std::fmt::Display for Units {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Units::Um => write!(f, "um"),
+            Units::Nm => write!(f, "nm"),
+        }
+    }
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableFormat {
+    Csv,
+    Tsv,
+    Json,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+pub enum ChartFormat {
+    Svg,
+    Csv,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+pub enum InterpolationChoice {
+    Linear,
+    Cubic,
+}
+
+impl From<InterpolationChoice> for Interpolation {
+    fn from(choice: InterpolationChoice) -> Self {
+        match choice {
+            InterpolationChoice::Linear => Interpolation::Linear,
+            InterpolationChoice::Cubic => Interpolation::Cubic,
+        }
+    }
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+pub enum ExtrapolationChoice {
+    /// Report an error for the wavelength
+    Error,
+
+    /// Use the value at the nearest end of the data's range
+    Clamp,
+
+    /// Evaluate formulas outside of their range and extend tables linearly
+    Extend,
+}
+
+impl From<ExtrapolationChoice> for Extrapolation {
+    fn from(choice: ExtrapolationChoice) -> Self {
+        match choice {
+            ExtrapolationChoice::Error => Extrapolation::Error,
+            ExtrapolationChoice::Clamp => Extrapolation::Clamp,
+            ExtrapolationChoice::Extend => Extrapolation::Extend,
+        }
+    }
+}
+
+/// Options that control how a store file is written.
+#[derive(clap::Args, Debug)]
+pub struct OutputArgs {
+    /// Compresses the output file. Compressed stores are detected
+    /// automatically when they are read.
+    #[arg(long, value_name = "METHOD")]
+    pub compress: Option<CompressionChoice>,
+
+    /// The compression level: 0-9 for gzip (default 6) and up to 22 for zstd
+    /// (default 3)
+    #[arg(
+        long,
+        value_name = "LEVEL",
+        requires = "compress",
+        allow_negative_numbers = true
+    )]
+    pub level: Option<i32>,
+
+    /// The precision of tabulated data in compact stores [default: f64]
+    #[arg(long, value_name = "PRECISION")]
+    pub precision: Option<PrecisionChoice>,
+}
+
+impl OutputArgs {
+    /// Checks that the options apply to the format of the store.
+    ///
+    /// # Errors
+    /// - If a precision is given for a store that is not compact.
+    fn check(&self, format: &Format) -> Result<()> {
+        if self.precision.is_some() && !matches!(format, Format::Compact) {
+            return Err(anyhow::anyhow!(
+                "A precision is given for a {} store, but only compact stores have one",
+                format
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// A configuration file for `ria store` that describes the stores to build
+/// from the database.
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct StoreConfig {
+    /// The path to the database, relative to the configuration file.
+    path: Option<PathBuf>,
+
+    /// The stores to write.
+    #[serde(rename = "output", default)]
+    outputs: Vec<OutputConfig>,
+}
+
+impl StoreConfig {
+    /// Checks that the configuration describes at least one store and that
+    /// no two stores are written to the same file or to standard output.
+    ///
+    /// # Arguments
+    /// - `config_dir`: The directory of the configuration file, which relative
+    ///   paths are resolved against.
+    fn check(&self, config_dir: &Path) -> Result<()> {
+        if self.outputs.is_empty() {
+            return Err(anyhow::anyhow!(
+                "The configuration describes no stores; add an [[output]] table"
+            ));
+        }
+        let mut names = std::collections::HashSet::new();
+        for output in &self.outputs {
+            // Paths are compared without . components, so that a.json and
+            // ./a.json are the same file
+            let path: PathBuf = output
+                .output(config_dir)
+                .components()
+                .filter(|component| component != &std::path::Component::CurDir)
+                .collect();
+            if !names.insert(path) {
+                return Err(anyhow::anyhow!(
+                    "More than one store is written to {}",
+                    describe(&output.name)
+                ));
+            }
+            if output.level.is_some() && output.compress.is_none() {
+                return Err(anyhow::anyhow!(
+                    "The store {} gives a compression level but no compression method",
+                    output.name.display()
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// One store in a configuration file. The fields mirror the options of
+/// `ria store`, and relative paths are relative to the configuration file.
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+struct OutputConfig {
+    /// The file to write the store to, or - for standard output.
+    name: PathBuf,
+
+    #[serde(default = "OutputConfig::default_catalog")]
+    catalog: CatalogChoice,
+
+    /// The format of the store, or the format given on the command line.
+    format: Option<Format>,
+
+    compress: Option<CompressionChoice>,
+    level: Option<i32>,
+    precision: Option<PrecisionChoice>,
+    include: Option<PathBuf>,
+    exclude: Option<PathBuf>,
+    shelf: Option<String>,
+    book: Option<String>,
+    divider: Option<String>,
+    data_type: Option<DataTypeChoice>,
+
+    #[serde(default)]
+    has_k: bool,
+
+    #[serde(default)]
+    covers: Vec<f64>,
+}
+
+impl OutputConfig {
+    fn default_catalog() -> CatalogChoice {
+        CatalogChoice::NK
+    }
+
+    /// The format of the store, given the format on the command line.
+    fn format<'a>(&'a self, format: Option<&'a Format>) -> &'a Format {
+        self.format.as_ref().or(format).unwrap_or(&Format::Json)
+    }
+
+    /// The path of the store file.
+    fn output(&self, config_dir: &Path) -> PathBuf {
+        match is_stdio(&self.name) {
+            true => self.name.clone(),
+            false => config_dir.join(&self.name),
+        }
+    }
+
+    /// The options that `ria store` would be given for this store.
+    fn args(&self, config_dir: &Path) -> (SelectArgs, OutputArgs) {
+        let select_args = SelectArgs {
+            include: self.include.as_ref().map(|path| config_dir.join(path)),
+            exclude: self.exclude.as_ref().map(|path| config_dir.join(path)),
+            filter_args: FilterArgs {
+                shelf: self.shelf.clone(),
+                book: self.book.clone(),
+                divider: self.divider.clone(),
+                data_type: self.data_type,
+                has_k: self.has_k,
+                covers: self.covers.clone(),
+            },
+        };
+        let output_args = OutputArgs {
+            compress: self.compress.clone(),
+            level: self.level,
+            precision: self.precision,
+        };
+        (select_args, output_args)
+    }
+}
+
+#[derive(ValueEnum, Deserialize, Debug, Clone)]
+#[serde(rename_all = "lowercase")]
+pub enum CompressionChoice {
+    Gzip,
+    Zstd,
+}
+
+impl From<&CompressionChoice> for Compression {
+    fn from(choice: &CompressionChoice) -> Self {
+        match choice {
+            CompressionChoice::Gzip => Compression::Gzip,
+            CompressionChoice::Zstd => Compression::Zstd,
+        }
+    }
+}
+
+#[derive(ValueEnum, Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum PrecisionChoice {
+    F64,
+    F32,
+}
+
+impl From<PrecisionChoice> for TablePrecision {
+    fn from(choice: PrecisionChoice) -> Self {
+        match choice {
+            PrecisionChoice::F64 => TablePrecision::F64,
+            PrecisionChoice::F32 => TablePrecision::F32,
+        }
+    }
 }
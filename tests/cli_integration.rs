@@ -2,7 +2,9 @@ use std::env;
 use std::fs;
 use std::io::Write;
 use std::path::PathBuf;
-use std::process::Command;
+use std::process::{Command, Stdio};
+
+use approx::assert_abs_diff_eq;
 
 fn db_path() -> PathBuf {
     PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("refractiveindex.info-database/database")
@@ -12,6 +14,111 @@ fn ria() -> Command {
     Command::new(env!("CARGO_BIN_EXE_ria"))
 }
 
+/// Creates a miniature database with two glasses and one metal in a temporary
+/// directory, so that tests do not depend on the database submodule.
+fn fixture_db(name: &str) -> PathBuf {
+    let root = env::temp_dir().join(format!("ria_test_db_{}", name));
+    fs::create_dir_all(root.join("data/main/Ag")).expect("failed to create fixture");
+    fs::create_dir_all(root.join("data/specs/schott")).expect("failed to create fixture");
+
+    fs::write(
+        root.join("catalog-nk.yml"),
+        r#"
+- SHELF: main
+  name: "MAIN - simple inorganic materials"
+  content:
+    - DIVIDER: "Ag - Silver"
+    - BOOK: Ag
+      name: "Ag (Silver)"
+      content:
+        - DIVIDER: "Experimental data: bulk, thick film"
+        - PAGE: Johnson
+          name: "Johnson and Christy 1972: n,k 0.188–1.94 µm"
+          data: "main/Ag/Johnson.yml"
+- SHELF: glass
+  name: "GLASS - optical glasses"
+  content:
+    - DIVIDER: "SCHOTT - optical glass"
+    - BOOK: BK7
+      name: "N-BK7"
+      content:
+        - PAGE: SCHOTT
+          name: "SCHOTT N-BK7"
+          data: "specs/schott/N-BK7.yml"
+    - BOOK: SF11
+      name: "N-SF11"
+      content:
+        - PAGE: SCHOTT
+          name: "SCHOTT N-SF11"
+          data: "specs/schott/N-SF11.yml"
+"#,
+    )
+    .expect("failed to write fixture catalog");
+
+    fs::write(
+        root.join("catalog-n2.yml"),
+        r#"
+- SHELF: glass
+  name: "GLASS - optical glasses"
+  content:
+    - BOOK: BK7
+      name: "N-BK7"
+      content:
+        - PAGE: Milam
+          name: "Milam 1998: n2 1.064 µm"
+          data: "n2/glass/BK7/Milam.yml"
+"#,
+    )
+    .expect("failed to write fixture catalog");
+    fs::create_dir_all(root.join("data/n2/glass/BK7")).expect("failed to create fixture");
+    fs::write(
+        root.join("data/n2/glass/BK7/Milam.yml"),
+        "REFERENCES: \"D. Milam 1998\"\nCOMMENTS: \"\"\nDATA:\n  - type: tabulated n2\n    unit: cm2/W\n    data: |\n        0.532 3.4e-16\n        1.064 3.1e-16\nSPECS:\n  method: Z-scan\n",
+    )
+    .expect("failed to write fixture material");
+
+    fs::write(
+        root.join("data/main/Ag/Johnson.yml"),
+        "REFERENCES: \"P. B. Johnson and R. W. Christy\"\nCOMMENTS: \"Room temperature\"\nDATA:\n  - type: tabulated nk\n    data: |\n        0.4 0.05 2.1\n        0.5 0.05 3.1\n        0.6 0.06 4.0\n        0.7 0.14 4.5\n",
+    )
+    .expect("failed to write fixture material");
+    fs::write(
+        root.join("data/specs/schott/N-BK7.yml"),
+        "REFERENCES: \"SCHOTT Zemax catalog\"\nCOMMENTS: \"\"\nDATA:\n  - type: formula 2\n    wavelength_range: 0.3 2.5\n    coefficients: 0 1.03961212 0.00600069867 0.231792344 0.0200179144 1.01046945 103.560653\nSPECS:\n  nd: 1.5168\n  Vd: 64.17\n  glass_status: standard\n",
+    )
+    .expect("failed to write fixture material");
+    fs::write(
+        root.join("data/specs/schott/N-SF11.yml"),
+        "REFERENCES: \"SCHOTT Zemax catalog\"\nCOMMENTS: \"\"\nDATA:\n  - type: formula 2\n    wavelength_range: 0.37 2.5\n    coefficients: 0 1.73759695 0.013188707 0.313747346 0.0623068142 1.89878101 155.23629\nSPECS:\n  nd: 1.78472\n  Vd: 25.68\n",
+    )
+    .expect("failed to write fixture material");
+
+    root
+}
+
+/// Builds a JSON store from the fixture database.
+fn fixture_store(name: &str) -> PathBuf {
+    let db = fixture_db(name);
+    let output = env::temp_dir().join(format!("ria_test_store_{}.dat", name));
+    let status = ria()
+        .args([
+            "store",
+            "--path",
+            db.to_str().unwrap(),
+            "--output",
+            output.to_str().unwrap(),
+        ])
+        .output()
+        .expect("failed to run ria store")
+        .status;
+    assert!(
+        status.success(),
+        "ria store failed for the fixture database"
+    );
+    fs::remove_dir_all(&db).ok();
+    output
+}
+
 #[test]
 fn test_store_nk_creates_json() {
     let output = env::temp_dir().join("ria_test_store_nk.dat");
@@ -170,3 +277,1110 @@ fn test_store_with_exclude_filter() {
     fs::remove_file(&exclude_file).ok();
     fs::remove_file(&output).ok();
 }
+
+#[test]
+fn test_migrate_v1_store() {
+    let input = env::temp_dir().join("ria_test_migrate_v1.dat");
+    let output = env::temp_dir().join("ria_test_migrate_v1_out.dat");
+
+    // A store as written by ria 1.x, whose materials have no divider labels
+    fs::write(
+        &input,
+        r#"{"inner":{"main:Ag:Johnson":{"shelf":"main","book":"Ag (Silver)","page":"Johnson","comments":"","references":"","data":[{"Formula2":{"wavelength_range":[0.3,2.5],"c":[0.0,1.03961212,0.00600069867]}}]}}}"#,
+    )
+    .expect("failed to write input store");
+
+    let migrated = ria()
+        .args([
+            "migrate",
+            "--input",
+            input.to_str().unwrap(),
+            "--output",
+            output.to_str().unwrap(),
+        ])
+        .output()
+        .expect("failed to run ria migrate");
+    assert!(migrated.status.success(), "ria migrate failed");
+    // The catalog is not recorded in the 1.x layout
+    assert!(String::from_utf8(migrated.stderr)
+        .unwrap()
+        .contains("assumed to be from the nk catalog"));
+
+    let content = fs::read_to_string(&output).expect("output file not found");
+    let json: serde_json::Value = serde_json::from_str(&content).expect("invalid JSON output");
+    let material = &json["inner"]["main:Ag:Johnson"];
+    assert_eq!(material["book"], "Ag (Silver)");
+    assert!(material["shelf_divider"].is_null());
+    assert!(material.get("book_divider").is_some());
+
+    fs::remove_file(&input).ok();
+    fs::remove_file(&output).ok();
+}
+
+#[test]
+fn test_store_mmap_then_validate() {
+    let db = fixture_db("mmap");
+    let output = env::temp_dir().join("ria_test_mmap.dat");
+    let store_status = ria()
+        .args([
+            "-f",
+            "mmap",
+            "store",
+            "--path",
+            db.to_str().unwrap(),
+            "--output",
+            output.to_str().unwrap(),
+        ])
+        .status()
+        .expect("failed to run ria store");
+    assert!(store_status.success(), "ria store (mmap) failed");
+
+    let store = lib_ria::IndexedStore::open(&output).expect("invalid indexed store");
+    let keys: Vec<&str> = store.keys().collect();
+    assert_eq!(
+        keys,
+        ["glass:BK7:SCHOTT", "glass:SF11:SCHOTT", "main:Ag:Johnson"]
+    );
+    let material = store.get("glass:BK7:SCHOTT").unwrap().unwrap();
+    assert_eq!(material.book, "N-BK7");
+
+    let validate_status = ria()
+        .args([
+            "-f",
+            "mmap",
+            "validate",
+            "--input",
+            output.to_str().unwrap(),
+        ])
+        .status()
+        .expect("failed to run ria validate");
+    assert!(validate_status.success(), "ria validate (mmap) failed");
+
+    // Commands that look up single keys map the file instead of reading it
+    let get = ria()
+        .args([
+            "get",
+            "glass:BK7:SCHOTT",
+            "--input",
+            output.to_str().unwrap(),
+        ])
+        .output()
+        .expect("failed to run ria get");
+    assert!(get.status.success(), "ria get (mmap) failed");
+    assert!(String::from_utf8(get.stdout)
+        .unwrap()
+        .contains("Book:          N-BK7"));
+    assert!(String::from_utf8(get.stderr)
+        .unwrap()
+        .contains("as a memory-mapped mmap store of 3 materials"));
+
+    fs::remove_file(&output).ok();
+    fs::remove_dir_all(&db).ok();
+}
+
+#[test]
+fn test_store_compressed_then_validate() {
+    let db = fixture_db("compressed");
+    for (method, magic) in [
+        ("gzip", &[0x1f, 0x8b][..]),
+        ("zstd", &[0x28, 0xb5, 0x2f, 0xfd]),
+    ] {
+        let output = env::temp_dir().join(format!("ria_test_compressed.{}", method));
+        let store_status = ria()
+            .args([
+                "store",
+                "--path",
+                db.to_str().unwrap(),
+                "--output",
+                output.to_str().unwrap(),
+                "--compress",
+                method,
+                "--level",
+                "9",
+            ])
+            .status()
+            .expect("failed to run ria store");
+        assert!(store_status.success(), "ria store ({}) failed", method);
+
+        let content = fs::read(&output).expect("output file not found");
+        assert!(content.starts_with(magic), "output is not {}", method);
+        let store = lib_ria::Store::from_bytes(&content).expect("invalid compressed store");
+        assert!(store.get("main:Ag:Johnson").is_some());
+
+        let validate_status = ria()
+            .args(["validate", "--input", output.to_str().unwrap()])
+            .status()
+            .expect("failed to run ria validate");
+        assert!(
+            validate_status.success(),
+            "ria validate ({}) failed",
+            method
+        );
+
+        fs::remove_file(&output).ok();
+    }
+    fs::remove_dir_all(&db).ok();
+}
+
+#[test]
+fn test_store_compact_then_validate() {
+    let db = fixture_db("compact");
+    let output = env::temp_dir().join("ria_test_compact.dat");
+    let store_status = ria()
+        .args([
+            "-f",
+            "compact",
+            "store",
+            "--path",
+            db.to_str().unwrap(),
+            "--output",
+            output.to_str().unwrap(),
+            "--precision",
+            "f32",
+        ])
+        .status()
+        .expect("failed to run ria store");
+    assert!(store_status.success(), "ria store (compact) failed");
+
+    let content = fs::read(&output).expect("output file not found");
+    let store = lib_ria::Store::from_bytes(&content).expect("invalid compact store");
+    let n = store.get("glass:BK7:SCHOTT").unwrap().n(0.5876).unwrap();
+    assert_abs_diff_eq!(n, 1.5168, epsilon = 1e-4);
+
+    let validate_status = ria()
+        .args([
+            "-f",
+            "compact",
+            "validate",
+            "--input",
+            output.to_str().unwrap(),
+        ])
+        .status()
+        .expect("failed to run ria validate");
+    assert!(validate_status.success(), "ria validate (compact) failed");
+
+    fs::remove_file(&output).ok();
+    fs::remove_dir_all(&db).ok();
+}
+
+#[test]
+fn test_get_prints_summary() {
+    let store = fixture_store("get");
+    let output = ria()
+        .args(["get", "main:Ag:Johnson", "--input", store.to_str().unwrap()])
+        .output()
+        .expect("failed to run ria get");
+    assert!(output.status.success(), "ria get failed");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Book:          Ag (Silver)"));
+    assert!(stdout.contains("Shelf divider: Ag - Silver"));
+    assert!(stdout.contains("tabulated nk: 0.4-0.7 µm, 4 points"));
+    // Every command that reads a store reports its format
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("as a json store (detected)"));
+
+    let output = ria()
+        .args([
+            "get",
+            "glass:BK7:SCHOTT",
+            "--input",
+            store.to_str().unwrap(),
+            "--json",
+        ])
+        .output()
+        .expect("failed to run ria get");
+    assert!(output.status.success(), "ria get --json failed");
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).expect("invalid JSON");
+    assert_eq!(json["book"], "N-BK7");
+    assert!(json["book_divider"].is_null());
+    assert_eq!(json["data"][0]["type"], "formula 2");
+    assert_eq!(json["data"][0]["coefficients"], 7);
+    assert_eq!(json["data"][0]["wavelength_range"][1], 2.5);
+
+    let output = ria()
+        .args(["get", "main:Ag:Missing", "--input", store.to_str().unwrap()])
+        .output()
+        .expect("failed to run ria get");
+    assert!(
+        !output.status.success(),
+        "ria get succeeded for a missing key"
+    );
+
+    fs::remove_file(&store).ok();
+}
+
+#[test]
+fn test_eval_writes_table() {
+    let store = fixture_store("eval");
+    let output = ria()
+        .args([
+            "eval",
+            "glass:BK7:SCHOTT",
+            "--input",
+            store.to_str().unwrap(),
+            "--at",
+            "0.5876,3.0",
+        ])
+        .output()
+        .expect("failed to run ria eval");
+    assert!(output.status.success(), "ria eval failed");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines[0], "wavelength_um,n,k,error");
+    let n: f64 = lines[1].split(',').nth(1).unwrap().parse().unwrap();
+    assert_abs_diff_eq!(n, 1.5168, epsilon = 1e-4);
+    assert!(lines[2].starts_with("3,,,\""));
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("1 of 2 wavelengths could not be evaluated"));
+
+    let output = ria()
+        .args([
+            "eval",
+            "main:Ag:Johnson",
+            "--input",
+            store.to_str().unwrap(),
+            "--from",
+            "400",
+            "--to",
+            "700",
+            "--step",
+            "100",
+            "--units",
+            "nm",
+            "--output-format",
+            "json",
+        ])
+        .output()
+        .expect("failed to run ria eval");
+    assert!(
+        output.status.success(),
+        "ria eval --output-format json failed"
+    );
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).expect("invalid JSON");
+    let rows = json.as_array().unwrap();
+    assert_eq!(rows.len(), 4);
+    assert_eq!(rows[3]["wavelength"], 700.0);
+    assert!(rows[3]["k"].is_number());
+    assert!(rows[3].get("error").is_none());
+    fs::remove_file(&store).ok();
+}
+
+#[test]
+fn test_eval_rejects_huge_grid() {
+    let store = fixture_store("eval_huge_grid");
+    let output = ria()
+        .args([
+            "eval",
+            "glass:BK7:SCHOTT",
+            "--input",
+            store.to_str().unwrap(),
+            "--from",
+            "0.2",
+            "--to",
+            "20",
+            "--step",
+            "1e-12",
+        ])
+        .output()
+        .expect("failed to run ria eval");
+    assert!(!output.status.success(), "ria eval accepted a huge grid");
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("more than the limit of 1000000"));
+    fs::remove_file(&store).ok();
+}
+
+#[test]
+fn test_list_and_search() {
+    let store = fixture_store("list");
+    let run = |args: &[&str]| {
+        let output = ria()
+            .args(args)
+            .args(["--input", store.to_str().unwrap()])
+            .output()
+            .expect("failed to run ria");
+        assert!(output.status.success(), "ria {:?} failed", args);
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    assert_eq!(
+        run(&["list", "--keys-only"]),
+        "glass:BK7:SCHOTT\nglass:SF11:SCHOTT\nmain:Ag:Johnson\n"
+    );
+    assert_eq!(
+        run(&["list", "--shelf", "glass", "--book", "bk7"]),
+        "glass:BK7:SCHOTT\tN-BK7: SCHOTT N-BK7\n"
+    );
+    assert_eq!(
+        run(&["list", "--divider", "thick film", "--keys-only"]),
+        "main:Ag:Johnson\n"
+    );
+    assert_eq!(run(&["list", "--shelf", "other"]), "");
+
+    assert_eq!(
+        run(&["search", "n-sf11", "--keys-only"]),
+        "glass:SF11:SCHOTT\n"
+    );
+    assert_eq!(
+        run(&["search", "silver", "--keys-only"]),
+        "main:Ag:Johnson\n"
+    );
+    fs::remove_file(&store).ok();
+}
+
+#[test]
+fn test_find_by_properties() {
+    let store = fixture_store("find");
+    let run = |args: &[&str]| {
+        let output = ria()
+            .args(args)
+            .args(["--input", store.to_str().unwrap(), "--keys-only"])
+            .output()
+            .expect("failed to run ria find");
+        assert!(output.status.success(), "ria {:?} failed", args);
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    assert_eq!(
+        run(&["find", "--n", "1.52", "--tol", "0.01"]),
+        "glass:BK7:SCHOTT\n"
+    );
+    assert_eq!(
+        run(&["find", "--n", "1.7", "--tol", "0.3"]),
+        "glass:SF11:SCHOTT\nglass:BK7:SCHOTT\n"
+    );
+    assert_eq!(
+        run(&["find", "--vd", "25.7", "--vd-tol", "0.5"]),
+        "glass:SF11:SCHOTT\n"
+    );
+    // Ag's data cover 0.4-0.7 µm only
+    assert_eq!(
+        run(&["find", "--k", "3", "--k-tol", "2", "--at", "0.45,0.65"]),
+        "main:Ag:Johnson\n"
+    );
+    assert_eq!(
+        run(&["find", "--k", "3", "--k-tol", "2", "--at", "0.8"]),
+        ""
+    );
+
+    let output = ria()
+        .args(["find", "--input", store.to_str().unwrap()])
+        .output()
+        .expect("failed to run ria find");
+    assert!(!output.status.success());
+    fs::remove_file(&store).ok();
+}
+
+#[test]
+fn test_substitutes() {
+    let store = fixture_store("substitutes");
+    let output = ria()
+        .args([
+            "substitutes",
+            "glass:BK7:SCHOTT",
+            "--input",
+            store.to_str().unwrap(),
+        ])
+        .output()
+        .expect("failed to run ria substitutes");
+    assert!(output.status.success(), "ria substitutes failed");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines[0], "key\tnd\tvd\tpgf\trms\tdistance");
+    // Ag is on another shelf, so N-SF11 is the only candidate
+    assert_eq!(lines.len(), 2);
+    assert!(lines[1].starts_with("glass:SF11:SCHOTT\t1.78"));
+
+    let output = ria()
+        .args([
+            "substitutes",
+            "main:Ag:Missing",
+            "--input",
+            store.to_str().unwrap(),
+        ])
+        .output()
+        .expect("failed to run ria substitutes");
+    assert!(!output.status.success());
+    fs::remove_file(&store).ok();
+}
+
+#[test]
+fn test_glass_map() {
+    let store = fixture_store("glass_map");
+    let svg = env::temp_dir().join("ria_test_glass_map.svg");
+    let output = ria()
+        .args([
+            "glass-map",
+            "--input",
+            store.to_str().unwrap(),
+            "--output",
+            svg.to_str().unwrap(),
+            "--highlight",
+            "glass:SF11:SCHOTT",
+            "--shelf",
+            "glass",
+        ])
+        .output()
+        .expect("failed to run ria glass-map");
+    assert!(output.status.success(), "ria glass-map failed");
+    let contents = fs::read_to_string(&svg).unwrap();
+    assert!(contents.starts_with("<svg"));
+    assert_eq!(contents.matches("<circle").count(), 2);
+    assert!(contents.contains(">N-SF11</text>"));
+
+    let output = ria()
+        .args([
+            "glass-map",
+            "--input",
+            store.to_str().unwrap(),
+            "--output-format",
+            "csv",
+        ])
+        .output()
+        .expect("failed to run ria glass-map");
+    assert!(
+        output.status.success(),
+        "ria glass-map --output-format csv failed"
+    );
+    let csv = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines[0], "key,book,nd,vd");
+    assert_eq!(lines.len(), 4);
+
+    fs::remove_file(&store).ok();
+    fs::remove_file(&svg).ok();
+}
+
+#[test]
+fn test_plot() {
+    let store = fixture_store("plot");
+    let svg = env::temp_dir().join("ria_test_plot.svg");
+    let output = ria()
+        .args([
+            "plot",
+            "glass:BK7:SCHOTT",
+            "main:Ag:Johnson",
+            "--input",
+            store.to_str().unwrap(),
+            "--output",
+            svg.to_str().unwrap(),
+            "--log-k",
+        ])
+        .output()
+        .expect("failed to run ria plot");
+    assert!(output.status.success(), "ria plot failed");
+    let contents = fs::read_to_string(&svg).unwrap();
+    assert!(contents.starts_with("<svg"));
+    assert_eq!(contents.matches("<polyline").count(), 3);
+    assert!(contents.contains("k (log)"));
+
+    let output = ria()
+        .args([
+            "plot",
+            "main:Ag:Missing",
+            "--input",
+            store.to_str().unwrap(),
+        ])
+        .output()
+        .expect("failed to run ria plot");
+    assert!(!output.status.success());
+
+    fs::remove_file(&store).ok();
+    fs::remove_file(&svg).ok();
+}
+
+#[test]
+fn test_convert_between_formats() {
+    let store = fixture_store("convert");
+    let bitcode = env::temp_dir().join("ria_test_convert.bitcode");
+    let json = env::temp_dir().join("ria_test_convert.json");
+    let include = env::temp_dir().join("ria_test_convert_include.txt");
+    fs::write(&include, "glass:BK7:SCHOTT\nmain:Ag:Johnson\n").unwrap();
+
+    let output = ria()
+        .args([
+            "convert",
+            "--from",
+            "json",
+            "--to",
+            "bitcode",
+            store.to_str().unwrap(),
+            bitcode.to_str().unwrap(),
+            "--include",
+            include.to_str().unwrap(),
+            "--compress",
+            "zstd",
+        ])
+        .output()
+        .expect("failed to run ria convert");
+    assert!(output.status.success(), "ria convert to bitcode failed");
+
+    // The input format is detected when --from is omitted
+    let output = ria()
+        .args([
+            "convert",
+            "--to",
+            "json",
+            bitcode.to_str().unwrap(),
+            json.to_str().unwrap(),
+        ])
+        .output()
+        .expect("failed to run ria convert");
+    assert!(output.status.success(), "ria convert to json failed");
+
+    let converted: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&json).unwrap()).unwrap();
+    let mut keys: Vec<&String> = converted["inner"].as_object().unwrap().keys().collect();
+    keys.sort();
+    assert_eq!(keys, ["glass:BK7:SCHOTT", "main:Ag:Johnson"]);
+
+    // Only compact stores have a precision
+    let output = ria()
+        .args([
+            "convert",
+            "--to",
+            "json",
+            bitcode.to_str().unwrap(),
+            json.to_str().unwrap(),
+            "--precision",
+            "f32",
+        ])
+        .output()
+        .expect("failed to run ria convert");
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("A precision is given for a json store"));
+
+    for file in [&store, &bitcode, &json, &include] {
+        fs::remove_file(file).ok();
+    }
+}
+
+#[test]
+fn test_store_with_patterns_and_both_filters() {
+    let db = fixture_db("patterns");
+    let output = env::temp_dir().join("ria_test_store_patterns.dat");
+    let include = env::temp_dir().join("ria_test_patterns_include.txt");
+    let exclude = env::temp_dir().join("ria_test_patterns_exclude.txt");
+    fs::write(
+        &include,
+        "# All SCHOTT glasses\nglass:*:SCHOTT\n\nregex:^main:A[gu]:\nmain:Ag:Jonson\n",
+    )
+    .unwrap();
+    fs::write(&exclude, "glass:SF11:*\n").unwrap();
+
+    let result = ria()
+        .args([
+            "store",
+            "--path",
+            db.to_str().unwrap(),
+            "--output",
+            output.to_str().unwrap(),
+            "--include",
+            include.to_str().unwrap(),
+            "--exclude",
+            exclude.to_str().unwrap(),
+        ])
+        .output()
+        .expect("failed to run ria store");
+    assert!(result.status.success(), "ria store failed");
+    let stderr = String::from_utf8(result.stderr).unwrap();
+    assert!(stderr.contains("main:Ag:Jonson matches no key"));
+    assert!(!stderr.contains("glass:*:SCHOTT"));
+
+    let store: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&output).unwrap()).unwrap();
+    let mut keys: Vec<&String> = store["inner"].as_object().unwrap().keys().collect();
+    keys.sort();
+    assert_eq!(keys, ["glass:BK7:SCHOTT", "main:Ag:Johnson"]);
+
+    fs::remove_dir_all(&db).ok();
+    for file in [&output, &include, &exclude] {
+        fs::remove_file(file).ok();
+    }
+}
+
+#[test]
+fn test_store_with_property_filters() {
+    let db = fixture_db("property_filters");
+    let output = env::temp_dir().join("ria_test_store_property_filters.dat");
+    let run = |args: &[&str]| {
+        let result = ria()
+            .args([
+                "store",
+                "--path",
+                db.to_str().unwrap(),
+                "--output",
+                output.to_str().unwrap(),
+            ])
+            .args(args)
+            .output()
+            .expect("failed to run ria store");
+        assert!(result.status.success(), "ria store {:?} failed", args);
+        let store: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&output).unwrap()).unwrap();
+        let mut keys: Vec<String> = store["inner"]
+            .as_object()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect();
+        keys.sort();
+        keys
+    };
+
+    assert_eq!(run(&["--has-k"]), ["main:Ag:Johnson"]);
+    assert_eq!(
+        run(&["--data-type", "formula", "--shelf", "glass"]),
+        ["glass:BK7:SCHOTT", "glass:SF11:SCHOTT"]
+    );
+    // N-SF11 starts at 0.37 µm
+    assert_eq!(run(&["--covers", "0.3,0.7"]), ["glass:BK7:SCHOTT"]);
+    assert_eq!(
+        run(&["--data-type", "tabulated", "--divider", "silver"]),
+        ["main:Ag:Johnson"]
+    );
+
+    fs::remove_dir_all(&db).ok();
+    fs::remove_file(&output).ok();
+}
+
+#[test]
+fn test_validate_reports_issues() {
+    let store = fixture_store("validate");
+    let validate = |args: &[&str]| {
+        ria()
+            .args(["validate", "--input", store.to_str().unwrap()])
+            .args(args)
+            .output()
+            .expect("failed to run ria validate")
+    };
+
+    let output = validate(&[]);
+    assert!(output.status.success(), "ria validate failed");
+    assert!(String::from_utf8(output.stdout)
+        .unwrap()
+        .contains("Checked 3 materials: 0 errors and 0 warnings"));
+
+    // A single row is a warning, and a reversed range is an error
+    let mut json: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&store).unwrap()).unwrap();
+    json["inner"]["main:Ag:Johnson"]["data"][0]["TabulatedNK"]["data"] =
+        serde_json::json!([[0.5, 0.05, 3.1]]);
+    fs::write(&store, json.to_string()).unwrap();
+
+    assert!(validate(&[]).status.success());
+    let output = validate(&["--fail-on", "warnings"]);
+    assert!(!output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout
+        .contains("main:Ag:Johnson\n  warning: Data 1 (tabulated nk): The table has a single row"));
+
+    json["inner"]["glass:BK7:SCHOTT"]["data"][0]["Formula2"]["wavelength_range"] =
+        serde_json::json!([2.5, 0.3]);
+    fs::write(&store, json.to_string()).unwrap();
+
+    let output = validate(&[]);
+    assert!(!output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout
+        .contains("glass:BK7:SCHOTT\n  error: Data 1 (formula 2): The wavelength range 2.5-0.3"));
+    assert!(stdout.contains("Checked 3 materials: 1 errors and 1 warnings"));
+    fs::remove_file(&store).ok();
+}
+
+#[test]
+fn test_validate_checks_specs() {
+    let store = fixture_store("check_specs");
+    let validate = |args: &[&str]| {
+        ria()
+            .args([
+                "validate",
+                "--input",
+                store.to_str().unwrap(),
+                "--check-specs",
+            ])
+            .args(args)
+            .output()
+            .expect("failed to run ria validate")
+    };
+
+    let output = validate(&[]);
+    assert!(output.status.success(), "ria validate --check-specs failed");
+
+    let mut json: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&store).unwrap()).unwrap();
+    assert_eq!(json["inner"]["glass:BK7:SCHOTT"]["specs"]["vd"], 64.17);
+    json["inner"]["glass:BK7:SCHOTT"]["specs"]["nd"] = serde_json::json!(1.5178);
+    fs::write(&store, json.to_string()).unwrap();
+
+    let output = validate(&[]);
+    assert!(!output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("glass:BK7:SCHOTT\n  error: nd is 1.51680, but the SPECS give 1.5178"));
+
+    // A looser tolerance accepts the difference
+    assert!(validate(&["--nd-tol", "0.01"]).status.success());
+    fs::remove_file(&store).ok();
+}
+
+#[test]
+fn test_validate_detects_format() {
+    let store = fixture_store("detect");
+    let validate = |args: &[&str], input: &PathBuf| {
+        ria()
+            .args(args)
+            .args(["validate", "--input", input.to_str().unwrap()])
+            .output()
+            .expect("failed to run ria validate")
+    };
+
+    let outputs = [
+        (&["--to", "bitcode"][..], "bitcode store (detected)"),
+        (
+            &["--to", "compact", "--compress", "zstd"][..],
+            "compact store, compressed with zstd (detected)",
+        ),
+        (&["--to", "mmap"][..], "mmap store (detected)"),
+    ];
+    for (args, report) in outputs {
+        let converted = env::temp_dir().join("ria_test_detect_converted.dat");
+        let status = ria()
+            .arg("convert")
+            .args(args)
+            .args([store.to_str().unwrap(), converted.to_str().unwrap()])
+            .status()
+            .expect("failed to run ria convert");
+        assert!(status.success(), "ria convert {:?} failed", args);
+
+        let output = validate(&[], &converted);
+        assert!(
+            output.status.success(),
+            "ria validate failed for {:?}",
+            args
+        );
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        assert!(stdout.contains(report), "unexpected report: {}", stdout);
+        fs::remove_file(&converted).ok();
+    }
+
+    // --format overrides the detection
+    let output = validate(&["--format", "json"], &store);
+    assert!(output.status.success());
+    assert!(String::from_utf8(output.stdout)
+        .unwrap()
+        .contains("is a json store\n"));
+
+    // The extension decides when the contents are not conclusive
+    let bad = env::temp_dir().join("ria_test_detect_bad.json");
+    fs::write(&bad, [0, 1, 2]).unwrap();
+    let output = validate(&[], &bad);
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("was detected as a json store"));
+
+    // Data in no recognized format are reported as such
+    let unknown = env::temp_dir().join("ria_test_detect_unknown.dat");
+    fs::write(&unknown, "not a store").unwrap();
+    let output = validate(&[], &unknown);
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("Unrecognized store format"));
+
+    fs::remove_file(&store).ok();
+    fs::remove_file(&bad).ok();
+    fs::remove_file(&unknown).ok();
+}
+
+#[test]
+fn test_store_to_stdout_then_validate_from_stdin() {
+    let db = fixture_db("stdio");
+    let stored = ria()
+        .args(["-f", "bitcode", "store", "--path", db.to_str().unwrap()])
+        .args(["--output", "-", "--compress", "gzip"])
+        .output()
+        .expect("failed to run ria store");
+    assert!(stored.status.success(), "ria store -o - failed");
+    // Progress messages must not corrupt the store
+    assert!(String::from_utf8_lossy(&stored.stderr).contains("Writing store to stdout"));
+    assert_eq!(&stored.stdout[..2], &[0x1f, 0x8b]);
+
+    let mut validate = ria()
+        .args(["validate", "--input", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("failed to run ria validate");
+    validate
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(&stored.stdout)
+        .unwrap();
+    let output = validate.wait_with_output().unwrap();
+    assert!(output.status.success(), "ria validate -i - failed");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("stdin is a bitcode store, compressed with gzip (detected)"));
+    assert!(stdout.contains("Checked 3 materials"));
+
+    fs::remove_dir_all(&db).ok();
+}
+
+#[test]
+fn test_store_log_levels_and_format() {
+    let db = fixture_db("logging");
+    // A page whose data file is missing is skipped with a warning
+    let catalog = fs::read_to_string(db.join("catalog-nk.yml")).unwrap();
+    fs::write(
+        db.join("catalog-nk.yml"),
+        catalog.replace("specs/schott/N-SF11.yml", "specs/schott/missing.yml"),
+    )
+    .unwrap();
+    let output = env::temp_dir().join("ria_test_store_logging.dat");
+    let run = |flags: &[&str]| {
+        ria()
+            .args(flags)
+            .args(["store", "--path", db.to_str().unwrap()])
+            .args(["--output", output.to_str().unwrap()])
+            .output()
+            .expect("failed to run ria store")
+    };
+
+    let default = run(&[]);
+    assert!(default.status.success());
+    let stderr = String::from_utf8(default.stderr).unwrap();
+    assert!(stderr.contains("Writing store to"));
+    assert!(stderr.contains("warning: Skipping glass:SF11:SCHOTT"));
+    assert!(!stderr.contains("Changing directory"));
+    assert!(default.stdout.is_empty());
+
+    let verbose = run(&["-v"]);
+    let stderr = String::from_utf8(verbose.stderr).unwrap();
+    assert!(stderr.contains("debug: Changing directory to"));
+    assert!(stderr.contains("debug: Read glass:BK7:SCHOTT"));
+
+    let quiet = run(&["-q"]);
+    let stderr = String::from_utf8(quiet.stderr).unwrap();
+    assert!(!stderr.contains("Writing store to"));
+    assert!(stderr.contains("warning: Skipping"));
+    assert!(run(&["-qq"]).stderr.is_empty());
+
+    let json = run(&["--log-format", "json"]);
+    let stderr = String::from_utf8(json.stderr).unwrap();
+    for line in stderr.lines() {
+        assert!(line.starts_with("{\"level\":"), "not JSON: {}", line);
+    }
+    assert!(stderr.contains(r#""level":"warn","message":"Skipping glass:SF11:SCHOTT"#));
+
+    fs::remove_dir_all(&db).ok();
+    fs::remove_file(&output).ok();
+}
+
+#[test]
+fn test_store_from_config() {
+    let db = fixture_db("config");
+    let dir = env::temp_dir().join("ria_test_config");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("glasses.txt"), "glass:*:SCHOTT\n").unwrap();
+    let config = dir.join("ria.toml");
+    fs::write(
+        &config,
+        format!(
+            r#"
+path = "{}"
+
+[[output]]
+name = "all.json"
+
+[[output]]
+name = "glasses.dat"
+format = "compact"
+compress = "zstd"
+level = 19
+include = "glasses.txt"
+covers = [0.4, 0.7]
+"#,
+            db.display()
+        ),
+    )
+    .unwrap();
+
+    let output = ria()
+        .args(["store", "--config", config.to_str().unwrap()])
+        .output()
+        .expect("failed to run ria store");
+    assert!(output.status.success(), "ria store --config failed");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Writing 3 materials to"));
+    assert!(stderr.contains("Writing 2 materials to"));
+
+    let validate = |name: &str| {
+        let output = ria()
+            .args(["validate", "--input", dir.join(name).to_str().unwrap()])
+            .output()
+            .expect("failed to run ria validate");
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+    let stdout = validate("all.json");
+    assert!(stdout.contains("is a json store"));
+    assert!(stdout.contains("Checked 3 materials"));
+    let stdout = validate("glasses.dat");
+    assert!(stdout.contains("is a compact store, compressed with zstd"));
+    assert!(stdout.contains("Checked 2 materials"));
+
+    // Options of a single store cannot be mixed with a configuration
+    let output = ria()
+        .args(["store", "--config", config.to_str().unwrap()])
+        .args(["--catalog", "n2"])
+        .output()
+        .expect("failed to run ria store");
+    assert!(!output.status.success());
+
+    // The path on the command line takes precedence over the file
+    fs::write(
+        &config,
+        "path = \"missing\"\n[[output]]\nname = \"all.json\"\n",
+    )
+    .unwrap();
+    let output = ria()
+        .args(["store", "--config", config.to_str().unwrap()])
+        .args(["--path", db.to_str().unwrap()])
+        .output()
+        .expect("failed to run ria store");
+    assert!(output.status.success(), "ria store --config --path failed");
+
+    // Two stores cannot be written to the same file or to stdout
+    for names in [["a.json", "./a.json"], ["-", "-"]] {
+        fs::write(
+            &config,
+            format!(
+                "[[output]]\nname = \"{}\"\n[[output]]\nname = \"{}\"\n",
+                names[0], names[1]
+            ),
+        )
+        .unwrap();
+        let output = ria()
+            .args(["store", "--config", config.to_str().unwrap()])
+            .output()
+            .expect("failed to run ria store");
+        assert!(!output.status.success());
+        assert!(String::from_utf8(output.stderr)
+            .unwrap()
+            .contains("More than one store is written to"));
+    }
+
+    // Only compact stores have a precision
+    fs::write(
+        &config,
+        "[[output]]\nname = \"a.dat\"\nformat = \"bitcode\"\nprecision = \"f32\"\n",
+    )
+    .unwrap();
+    let output = ria()
+        .args(["store", "--config", config.to_str().unwrap()])
+        .output()
+        .expect("failed to run ria store");
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("A precision is given for a bitcode store"));
+
+    // Unknown keys are rejected
+    fs::write(
+        &config,
+        "[[output]]\nname = \"a.dat\"\nformats = \"json\"\n",
+    )
+    .unwrap();
+    let output = ria()
+        .args(["store", "--config", config.to_str().unwrap()])
+        .output()
+        .expect("failed to run ria store");
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("unknown field `formats`"));
+
+    fs::remove_dir_all(&db).ok();
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn test_store_combined_catalogs() {
+    let db = fixture_db("combined");
+    let output = env::temp_dir().join("ria_test_store_combined.dat");
+    let stored = ria()
+        .args(["store", "--path", db.to_str().unwrap(), "--catalog", "all"])
+        .args(["--output", output.to_str().unwrap()])
+        .output()
+        .expect("failed to run ria store");
+    assert!(stored.status.success(), "ria store --catalog all failed");
+    let stderr = String::from_utf8(stored.stderr).unwrap();
+    assert!(stderr.contains("Reading the nk catalog"));
+    assert!(stderr.contains("Reading the n2 catalog"));
+
+    let get = |key: &str| {
+        let output = ria()
+            .args(["get", key, "--input", output.to_str().unwrap()])
+            .output()
+            .expect("failed to run ria get");
+        assert!(output.status.success(), "ria get {} failed", key);
+        String::from_utf8(output.stdout).unwrap()
+    };
+    // The n2 data of a glass are found from the key of its nk data
+    let stdout = get("glass:BK7:SCHOTT");
+    assert!(stdout.contains("Catalog:       nk"));
+    assert!(stdout.contains("n2 data:\n  n2:glass:BK7:Milam"));
+    let stdout = get("n2:glass:BK7:Milam");
+    assert!(stdout.contains("Catalog:       n2"));
+    assert!(stdout.contains("tabulated n2: 0.532-1.064 µm, 2 points (Z-scan)"));
+    assert!(!stdout.contains("n2 data:"));
+
+    fs::remove_dir_all(&db).ok();
+    fs::remove_file(&output).ok();
+}
+
+#[test]
+fn test_store_keeps_unknown_data() {
+    let db = fixture_db("unknown");
+    fs::write(
+        db.join("data/main/Ag/Johnson.yml"),
+        "REFERENCES: \"P. B. Johnson and R. W. Christy\"\nCOMMENTS: \"\"\nCONDITIONS:\n  temperature: 293\nDATA:\n  - type: tabulated nk\n    data: |\n        0.4 0.05 2.1\n        0.5 0.05 3.1\n  - type: formula 42\n    wavelength_range: 0.3 2.5\n    coefficients: 1 2 3\n",
+    )
+    .unwrap();
+    let store = env::temp_dir().join("ria_test_store_unknown.dat");
+    let stored = ria()
+        .args(["store", "--path", db.to_str().unwrap()])
+        .args(["--output", store.to_str().unwrap()])
+        .output()
+        .expect("failed to run ria store");
+    assert!(stored.status.success(), "ria store failed");
+    let stderr = String::from_utf8(stored.stderr).unwrap();
+    assert!(stderr.contains("warning: Ignored the unknown key CONDITIONS of 1 page"));
+    assert!(stderr.contains(
+        "warning: Kept 1 set of data of the unsupported type \"formula 42\", which cannot be evaluated"
+    ));
+
+    let output = ria()
+        .args(["get", "main:Ag:Johnson", "--input", store.to_str().unwrap()])
+        .output()
+        .expect("failed to run ria get");
+    assert!(output.status.success(), "ria get failed");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("tabulated nk: 0.4-0.5 µm, 2 points"));
+    assert!(stdout.contains("formula 42: no wavelengths, not supported"));
+
+    let output = ria()
+        .args(["validate", "--input", store.to_str().unwrap()])
+        .output()
+        .expect("failed to run ria validate");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("warning: Data 2 (formula 42): The data type is not supported"));
+
+    fs::remove_dir_all(&db).ok();
+    fs::remove_file(&store).ok();
+}